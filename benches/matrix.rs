@@ -0,0 +1,59 @@
+use alexandria_common::{Matrix, Vector4};
+use alexandria_dx11::LHRowMajorMatrix;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const VECTOR_COUNT: usize = 10_000;
+
+// The loop through get and set that the SSE path replaced
+fn multiply_naive(lhs: &LHRowMajorMatrix, rhs: &LHRowMajorMatrix) -> LHRowMajorMatrix {
+    let mut ret = LHRowMajorMatrix::zero();
+    for i in 0..4 {
+        for j in 0..4 {
+            for k in 0..4 {
+                ret.set(i, j, ret.get(i, j) + lhs.get(i, k) * rhs.get(k, j));
+            }
+        }
+    }
+    ret
+}
+
+fn matrices() -> (LHRowMajorMatrix, LHRowMajorMatrix) {
+    (
+        LHRowMajorMatrix::translation(1.0, 2.0, 3.0) * LHRowMajorMatrix::rotation(0.3, 0.6, 0.9),
+        LHRowMajorMatrix::perspective(1.2, 16.0 / 9.0, 0.1, 100.0),
+    )
+}
+
+fn vectors() -> Vec<Vector4> {
+    (0..VECTOR_COUNT)
+        .map(|i| Vector4::new(i as f32, -(i as f32), 0.5, 1.0))
+        .collect()
+}
+
+fn multiply(c: &mut Criterion) {
+    let (lhs, rhs) = matrices();
+
+    c.bench_function("matrix * matrix", |b| {
+        b.iter(|| *black_box(&lhs) * *black_box(&rhs))
+    });
+    c.bench_function("matrix * matrix (naive)", |b| {
+        b.iter(|| multiply_naive(black_box(&lhs), black_box(&rhs)))
+    });
+}
+
+fn transform(c: &mut Criterion) {
+    let (matrix, _) = matrices();
+    let vector = Vector4::new(1.0, 2.0, 3.0, 1.0);
+
+    c.bench_function("matrix * vector", |b| {
+        b.iter(|| *black_box(&matrix) * *black_box(&vector))
+    });
+
+    let mut vectors = vectors();
+    c.bench_function("transform_vectors 10k", |b| {
+        b.iter(|| matrix.transform_vectors(black_box(&mut vectors)))
+    });
+}
+
+criterion_group!(benches, multiply, transform);
+criterion_main!(benches);
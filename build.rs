@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+// Finds the acsl package in Cargo.lock, as its version and source, which includes the git
+// revision
+fn acsl_version(lock: &str) -> Option<String> {
+    let package = lock
+        .split("[[package]]")
+        .find(|package| package.lines().any(|line| line.trim() == "name = \"acsl\""))?;
+
+    let fields: Vec<&str> = package
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("version") || line.starts_with("source"))
+        .collect();
+    Some(fields.join(", "))
+}
+
+// Cargo.lock lives at the root of the workspace being built, which contains the target
+// directory, or this crate when it is the workspace
+fn find_lock(directories: &[PathBuf]) -> Option<PathBuf> {
+    directories
+        .iter()
+        .flat_map(|directory| directory.ancestors())
+        .map(|directory| directory.join("Cargo.lock"))
+        .find(|path| path.is_file())
+}
+
+// Bakes the resolved acsl package into ACSL_VERSION, so shader cache keys change whenever acsl
+// does and an upgrade never loads bytecode compiled from its old output
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let directories = [
+        PathBuf::from(std::env::var_os("OUT_DIR").unwrap()),
+        PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR").unwrap()),
+    ];
+
+    let version = match find_lock(&directories) {
+        Some(lock) => {
+            println!("cargo:rerun-if-changed={}", lock.display());
+            std::fs::read_to_string(&lock)
+                .ok()
+                .and_then(|contents| acsl_version(&contents))
+        }
+        None => None,
+    };
+
+    let version = version.unwrap_or_else(|| {
+        println!(
+            "cargo:warning=acsl is missing from Cargo.lock, so the shader cache will not notice \
+             acsl upgrades"
+        );
+        "unknown".to_owned()
+    });
+    println!("cargo:rustc-env=ACSL_VERSION={}", version);
+}
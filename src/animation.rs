@@ -0,0 +1,306 @@
+use crate::Transform;
+use alexandria_common::Vector3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    // Catmull-Rom splines through translation and scale. Rotation is slerped.
+    Cubic,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe {
+    time: f32,
+    transform: Transform,
+}
+
+pub struct TransformTrack {
+    keyframes: Vec<Keyframe>,
+    interpolation: Interpolation,
+}
+
+// Catmull-Rom through keyframes at uneven times. The tangents at p1 and p2 are the slopes over
+// the neighbouring keyframes, scaled to the duration of the p1 to p2 segment, which reduces to
+// the usual uniform spline when the keyframes are evenly spaced.
+fn catmull_rom(points: [Vector3; 4], times: [f32; 4], t: f32) -> Vector3 {
+    let [p0, p1, p2, p3] = points;
+    let [time0, time1, time2, time3] = times;
+
+    let duration = time2 - time1;
+    let scale1 = duration / (time2 - time0);
+    let scale2 = duration / (time3 - time1);
+
+    let t2 = t * t;
+    let t3 = t2 * t;
+    let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+    let h10 = t3 - 2.0 * t2 + t;
+    let h01 = -2.0 * t3 + 3.0 * t2;
+    let h11 = t3 - t2;
+
+    let component = |p0: f32, p1: f32, p2: f32, p3: f32| {
+        h00 * p1 + h10 * (p2 - p0) * scale1 + h01 * p2 + h11 * (p3 - p1) * scale2
+    };
+
+    Vector3::new(
+        component(p0.x(), p1.x(), p2.x(), p3.x()),
+        component(p0.y(), p1.y(), p2.y(), p3.y()),
+        component(p0.z(), p1.z(), p2.z(), p3.z()),
+    )
+}
+
+impl Keyframe {
+    pub fn new(time: f32, transform: Transform) -> Self {
+        Keyframe { time, transform }
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+}
+
+impl TransformTrack {
+    pub fn new(interpolation: Interpolation) -> Self {
+        TransformTrack {
+            keyframes: Vec::new(),
+            interpolation,
+        }
+    }
+
+    // Keyframes are kept sorted by time. Adding a keyframe at an existing time replaces it.
+    pub fn add_keyframe(&mut self, time: f32, transform: Transform) {
+        let keyframe = Keyframe::new(time, transform);
+        match self
+            .keyframes
+            .binary_search_by(|keyframe| keyframe.time.total_cmp(&time))
+        {
+            Ok(index) => self.keyframes[index] = keyframe,
+            Err(index) => self.keyframes.insert(index, keyframe),
+        }
+    }
+
+    pub fn remove_keyframe(&mut self, index: usize) -> Option<Keyframe> {
+        if index < self.keyframes.len() {
+            Some(self.keyframes.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+    }
+
+    pub fn start_time(&self) -> Option<f32> {
+        self.keyframes.first().map(|keyframe| keyframe.time)
+    }
+
+    pub fn end_time(&self) -> Option<f32> {
+        self.keyframes.last().map(|keyframe| keyframe.time)
+    }
+
+    // Times outside of the track, including infinities, hold the first or last keyframe.
+    // Returns None for an empty track or a NaN time.
+    pub fn sample(&self, time: f32) -> Option<Transform> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+
+        if time.is_nan() {
+            return None;
+        }
+
+        if time <= first.time {
+            return Some(first.transform);
+        } else if time >= last.time {
+            return Some(last.transform);
+        }
+
+        // Index of the first keyframe after time, which is never the first keyframe here
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        let previous = next - 1;
+
+        let start = &self.keyframes[previous];
+        let end = &self.keyframes[next];
+        let t = (time - start.time) / (end.time - start.time);
+
+        Some(match self.interpolation {
+            Interpolation::Step => start.transform,
+            Interpolation::Linear => start.transform.interpolate(&end.transform, t),
+            Interpolation::Cubic => {
+                let before = &self.keyframes[previous.saturating_sub(1)];
+                let after = &self.keyframes[(next + 1).min(self.keyframes.len() - 1)];
+                let times = [before.time, start.time, end.time, after.time];
+
+                Transform::new(
+                    catmull_rom(
+                        [
+                            before.transform.translation(),
+                            start.transform.translation(),
+                            end.transform.translation(),
+                            after.transform.translation(),
+                        ],
+                        times,
+                        t,
+                    ),
+                    start
+                        .transform
+                        .rotation()
+                        .slerp(end.transform.rotation(), t),
+                    catmull_rom(
+                        [
+                            before.transform.scale(),
+                            start.transform.scale(),
+                            end.transform.scale(),
+                            after.transform.scale(),
+                        ],
+                        times,
+                        t,
+                    ),
+                )
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Quaternion;
+
+    fn at(x: f32) -> Transform {
+        Transform::new(
+            Vector3::new(x, 0.0, 0.0),
+            Quaternion::identity(),
+            Vector3::new(1.0, 1.0, 1.0),
+        )
+    }
+
+    fn track(interpolation: Interpolation, keyframes: &[(f32, f32)]) -> TransformTrack {
+        let mut track = TransformTrack::new(interpolation);
+        for (time, x) in keyframes {
+            track.add_keyframe(*time, at(*x));
+        }
+        track
+    }
+
+    fn sample_x(track: &TransformTrack, time: f32) -> f32 {
+        track.sample(time).unwrap().translation().x()
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn step() {
+        let track = track(Interpolation::Step, &[(0.0, 1.0), (1.0, 2.0), (3.0, 5.0)]);
+        assert_close(sample_x(&track, 0.5), 1.0);
+        assert_close(sample_x(&track, 1.0), 2.0);
+        assert_close(sample_x(&track, 2.9), 2.0);
+    }
+
+    #[test]
+    fn linear() {
+        let track = track(Interpolation::Linear, &[(0.0, 1.0), (1.0, 2.0), (3.0, 6.0)]);
+        assert_close(sample_x(&track, 0.25), 1.25);
+        assert_close(sample_x(&track, 1.0), 2.0);
+        assert_close(sample_x(&track, 2.0), 4.0);
+    }
+
+    #[test]
+    fn cubic_passes_through_keyframes() {
+        let track = track(
+            Interpolation::Cubic,
+            &[(0.0, 0.0), (1.0, 2.0), (2.0, 1.0), (3.0, 4.0)],
+        );
+        for (time, x) in [(0.0, 0.0), (1.0, 2.0), (2.0, 1.0), (3.0, 4.0)] {
+            assert_close(sample_x(&track, time), x);
+        }
+
+        // Evenly spaced keyframes give the uniform Catmull-Rom spline, here
+        // 0.5 * (2 * p1 + (p2 - p0) * t + (2 * p0 - 5 * p1 + 4 * p2 - p3) * t^2
+        // + (-p0 + 3 * p1 - 3 * p2 + p3) * t^3) with t = 0.5
+        let (p0, p1, p2, p3) = (0.0, 2.0, 1.0, 4.0);
+        let expected = 0.5
+            * (2.0 * p1
+                + (p2 - p0) * 0.5
+                + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * 0.25
+                + (-p0 + 3.0 * p1 - 3.0 * p2 + p3) * 0.125);
+        assert_close(sample_x(&track, 1.5), expected);
+    }
+
+    #[test]
+    fn cubic_uneven_keyframes_keep_constant_velocity() {
+        // A constant velocity of 2 units per second with unevenly spaced keyframes. Uniform
+        // tangents would overshoot between the closely spaced keyframes.
+        let track = track(
+            Interpolation::Cubic,
+            &[(0.0, 0.0), (0.1, 0.2), (2.0, 4.0), (2.2, 4.4), (5.0, 10.0)],
+        );
+        for time in [0.5, 1.0, 1.5, 2.1, 3.0, 4.5] {
+            assert_close(sample_x(&track, time), 2.0 * time);
+        }
+    }
+
+    #[test]
+    fn outside_the_track() {
+        for interpolation in [
+            Interpolation::Step,
+            Interpolation::Linear,
+            Interpolation::Cubic,
+        ] {
+            let track = track(interpolation, &[(1.0, 3.0), (2.0, 5.0)]);
+            assert_close(sample_x(&track, -1.0), 3.0);
+            assert_close(sample_x(&track, 10.0), 5.0);
+            assert_close(sample_x(&track, f32::NEG_INFINITY), 3.0);
+            assert_close(sample_x(&track, f32::INFINITY), 5.0);
+            assert!(track.sample(f32::NAN).is_none());
+        }
+    }
+
+    #[test]
+    fn single_and_no_keyframes() {
+        for interpolation in [
+            Interpolation::Step,
+            Interpolation::Linear,
+            Interpolation::Cubic,
+        ] {
+            let track = track(interpolation, &[(1.0, 7.0)]);
+            assert_close(sample_x(&track, 0.0), 7.0);
+            assert_close(sample_x(&track, 1.0), 7.0);
+            assert_close(sample_x(&track, 2.0), 7.0);
+            assert!(track.sample(f32::NAN).is_none());
+
+            assert!(TransformTrack::new(interpolation).sample(0.0).is_none());
+        }
+    }
+
+    #[test]
+    fn adding_at_an_existing_time_replaces() {
+        let mut track = track(Interpolation::Linear, &[(1.0, 1.0), (0.0, 0.0), (2.0, 2.0)]);
+        track.add_keyframe(1.0, at(10.0));
+        assert_eq!(track.keyframes().len(), 3);
+        assert_close(sample_x(&track, 1.0), 10.0);
+        assert_eq!(track.start_time(), Some(0.0));
+        assert_eq!(track.end_time(), Some(2.0));
+    }
+}
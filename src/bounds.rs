@@ -0,0 +1,141 @@
+use crate::LHRowMajorMatrix;
+use alexandria_common::{Matrix, Vector3};
+
+#[derive(Debug, Clone, Copy)]
+pub struct BoundingBox {
+    min: Vector3,
+    max: Vector3,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BoundingSphere {
+    center: Vector3,
+    radius: f32,
+}
+
+impl BoundingBox {
+    pub fn new(min: Vector3, max: Vector3) -> Self {
+        BoundingBox {
+            min: Vector3::new(
+                min.x().min(max.x()),
+                min.y().min(max.y()),
+                min.z().min(max.z()),
+            ),
+            max: Vector3::new(
+                min.x().max(max.x()),
+                min.y().max(max.y()),
+                min.z().max(max.z()),
+            ),
+        }
+    }
+
+    pub fn from_center_extents(center: Vector3, extents: Vector3) -> Self {
+        BoundingBox::new(
+            Vector3::new(
+                center.x() - extents.x(),
+                center.y() - extents.y(),
+                center.z() - extents.z(),
+            ),
+            Vector3::new(
+                center.x() + extents.x(),
+                center.y() + extents.y(),
+                center.z() + extents.z(),
+            ),
+        )
+    }
+
+    pub fn from_points(points: &[Vector3]) -> Option<Self> {
+        let first = points.first()?;
+        let mut min = [first.x(), first.y(), first.z()];
+        let mut max = min;
+        for point in &points[1..] {
+            let point = [point.x(), point.y(), point.z()];
+            for i in 0..3 {
+                min[i] = min[i].min(point[i]);
+                max[i] = max[i].max(point[i]);
+            }
+        }
+
+        Some(BoundingBox {
+            min: Vector3::new(min[0], min[1], min[2]),
+            max: Vector3::new(max[0], max[1], max[2]),
+        })
+    }
+
+    pub fn min(&self) -> Vector3 {
+        self.min
+    }
+
+    pub fn max(&self) -> Vector3 {
+        self.max
+    }
+
+    pub fn center(&self) -> Vector3 {
+        Vector3::new(
+            (self.min.x() + self.max.x()) / 2.0,
+            (self.min.y() + self.max.y()) / 2.0,
+            (self.min.z() + self.max.z()) / 2.0,
+        )
+    }
+
+    pub fn extents(&self) -> Vector3 {
+        Vector3::new(
+            (self.max.x() - self.min.x()) / 2.0,
+            (self.max.y() - self.min.y()) / 2.0,
+            (self.max.z() - self.min.z()) / 2.0,
+        )
+    }
+
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        point.x() >= self.min.x()
+            && point.x() <= self.max.x()
+            && point.y() >= self.min.y()
+            && point.y() <= self.max.y()
+            && point.z() >= self.min.z()
+            && point.z() <= self.max.z()
+    }
+
+    // Returns the box enclosing this box after it has been transformed by an affine matrix
+    pub fn transform(&self, matrix: &LHRowMajorMatrix) -> BoundingBox {
+        let min = [self.min.x(), self.min.y(), self.min.z()];
+        let max = [self.max.x(), self.max.y(), self.max.z()];
+
+        let mut new_min = [matrix.get(0, 3), matrix.get(1, 3), matrix.get(2, 3)];
+        let mut new_max = new_min;
+        for i in 0..3 {
+            for j in 0..3 {
+                let a = matrix.get(i, j) * min[j];
+                let b = matrix.get(i, j) * max[j];
+                new_min[i] += a.min(b);
+                new_max[i] += a.max(b);
+            }
+        }
+
+        BoundingBox {
+            min: Vector3::new(new_min[0], new_min[1], new_min[2]),
+            max: Vector3::new(new_max[0], new_max[1], new_max[2]),
+        }
+    }
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vector3, radius: f32) -> Self {
+        BoundingSphere {
+            center,
+            radius: radius.abs(),
+        }
+    }
+
+    pub fn center(&self) -> Vector3 {
+        self.center
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        let offset = point - self.center;
+        offset.dot(offset) <= self.radius * self.radius
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{ShaderConstantBufferDesc, ShaderStages};
+use alexandria_common::Input;
+use std::{cell::RefCell, ffi::CString, rc::Rc};
+
+// Resources are bound to the compute stage by including ShaderStages::COMPUTE in their stages,
+// or with set_active_compute_rw for unordered access
+pub struct ComputeShader {
+    compute_shader: win32::ID3D11ComputeShader,
+    thread_group_size: [u32; 3],
+    constant_buffers: Vec<ShaderConstantBufferDesc>,
+    device_context: Rc<RefCell<win32::ID3D11DeviceContext>>,
+}
+
+// The number of groups of group_size threads needed to cover threads. Panics if group_size is
+// zero, which no numthreads declaration can produce.
+pub const fn group_count(threads: u32, group_size: u32) -> u32 {
+    assert!(
+        group_size > 0,
+        "A thread group must have at least one thread"
+    );
+    threads.div_ceil(group_size)
+}
+
+impl ComputeShader {
+    // Compiles the compute_main entry point of ACSL source
+    pub fn new<S: AsRef<str>, I: Input>(
+        code: S,
+        window: &mut crate::Window<I>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let hlsl_code = acsl::compile_hlsl(code)?;
+        ComputeShader::from_hlsl(hlsl_code, window)
+    }
+
+    // Compiles the compute_main entry point of HLSL source
+    pub fn from_hlsl<S: Into<Vec<u8>>, I: Input>(
+        hlsl_code: S,
+        window: &mut crate::Window<I>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let shader_code = CString::new(hlsl_code)?;
+
+        let blob = crate::shader::compile(&shader_code, "compute_main", "cs_5_0")?;
+        let compute_shader = window.device().create_compute_shader(&blob)?;
+
+        let thread_group_size = crate::shader_reflection::reflect_thread_group_size(&blob)?;
+
+        let mut constant_buffers = Vec::new();
+        crate::shader_reflection::reflect_constant_buffers(
+            &blob,
+            ShaderStages::COMPUTE,
+            &mut constant_buffers,
+        )?;
+
+        Ok(ComputeShader {
+            compute_shader,
+            thread_group_size,
+            constant_buffers,
+            device_context: window.device_context().clone(),
+        })
+    }
+
+    // The numthreads declared by the shader
+    pub fn thread_group_size(&self) -> [u32; 3] {
+        self.thread_group_size
+    }
+
+    // The number of thread groups needed to cover a grid of threads. Reflection guarantees every
+    // dimension of the thread group size is at least one, so this never panics.
+    pub fn group_counts(&self, x: u32, y: u32, z: u32) -> [u32; 3] {
+        [
+            group_count(x, self.thread_group_size[0]),
+            group_count(y, self.thread_group_size[1]),
+            group_count(z, self.thread_group_size[2]),
+        ]
+    }
+
+    pub fn constant_buffers(&self) -> &[ShaderConstantBufferDesc] {
+        &self.constant_buffers
+    }
+
+    pub fn constant_buffer(&self, name: &str) -> Option<&ShaderConstantBufferDesc> {
+        self.constant_buffers
+            .iter()
+            .find(|constant_buffer| constant_buffer.name() == name)
+    }
+
+    pub fn set_active(&mut self) {
+        self.device_context
+            .borrow_mut()
+            .cs_set_shader(Some(&mut self.compute_shader));
+    }
+
+    pub fn clear_active(&mut self) {
+        self.device_context.borrow_mut().cs_set_shader(None);
+    }
+
+    // Runs x * y * z thread groups, with whichever resources are currently bound to the
+    // compute stage. D3D11 allows at most 65535 groups in each dimension.
+    pub fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        let mut device_context = self.device_context.borrow_mut();
+        device_context.cs_set_shader(Some(&mut self.compute_shader));
+        device_context.dispatch(x, y, z);
+    }
+
+    // Runs enough thread groups to cover a grid of x * y * z threads. The shader must ignore
+    // threads past the edge of the grid.
+    pub fn dispatch_threads(&mut self, x: u32, y: u32, z: u32) {
+        let [x, y, z] = self.group_counts(x, y, z);
+        self.dispatch(x, y, z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_counts_round_up() {
+        assert_eq!(group_count(0, 64), 0);
+        assert_eq!(group_count(1, 64), 1);
+        assert_eq!(group_count(64, 64), 1);
+        assert_eq!(group_count(65, 64), 2);
+        assert_eq!(group_count(u32::MAX, 1), u32::MAX);
+        assert_eq!(group_count(u32::MAX, 1024), 4194304);
+    }
+
+    #[test]
+    #[should_panic(expected = "A thread group must have at least one thread")]
+    fn zero_group_size_panics() {
+        group_count(100, 0);
+    }
+}
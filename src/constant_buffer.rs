@@ -1,14 +1,32 @@
+use crate::{
+    constant_shadow::ConstantShadow, hlsl_buffer_size, hlsl_bytes, HLSLType, ShaderStages,
+};
 use alexandria_common::Input;
-use std::{cell::RefCell, marker::PhantomData, mem::size_of, rc::Rc};
+use std::{cell::RefCell, marker::PhantomData, mem::size_of, ops::Range, rc::Rc};
 
-pub struct ConstantBuffer<T: Sized> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstantBufferUsage {
+    // Rewritten every upload with a WriteDiscard map
+    Dynamic,
+    // Updated on the GPU timeline, which allows partial updates on D3D11.1
+    Default,
+}
+
+pub struct ConstantBuffer<T: HLSLType> {
     constant_buffer: win32::ID3D11Buffer,
+    usage: ConstantBufferUsage,
     slot: usize,
+    stages: ShaderStages,
     phantom: PhantomData<T>,
     device_context: Rc<RefCell<win32::ID3D11DeviceContext>>,
+
+    // Only available for default buffers on devices which support partial updates
+    device_context1: Option<win32::ID3D11DeviceContext1>,
+
+    shadow: ConstantShadow,
 }
 
-impl<T: Sized> alexandria_common::ConstantBuffer<T> for ConstantBuffer<T> {
+impl<T: HLSLType> alexandria_common::ConstantBuffer<T> for ConstantBuffer<T> {
     type Window<I: Input> = Box<crate::Window<I>>;
 
     fn new<I: Input>(
@@ -16,71 +34,183 @@ impl<T: Sized> alexandria_common::ConstantBuffer<T> for ConstantBuffer<T> {
         slot: usize,
         window: &mut Self::Window<I>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let buffer_desc = win32::D3D11BufferDesc::new(
-            size_of::<T>() as u32,
-            win32::D3D11Usage::Dynamic,
-            &[win32::D3D11BindFlag::ConstantBuffer],
-            &[win32::D3D11CPUAccessFlag::Write],
-            &[],
-            0,
+        ConstantBuffer::new_with_usage(initial_data, slot, ConstantBufferUsage::Dynamic, window)
+    }
+
+    // Skips the upload when the data is unchanged since the last one
+    fn set_data(&mut self, new_data: T) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_data_range(new_data, 0..size_of::<T>())
+    }
+
+    fn set_slot(&mut self, slot: usize) {
+        self.slot = slot;
+    }
+
+    fn set_active(&mut self) {
+        self.stages.set_constant_buffers(
+            &mut self.device_context.borrow_mut(),
+            self.slot as u32,
+            &mut [Some(&mut self.constant_buffer)],
+        );
+    }
+
+    fn clear_active(&mut self) {
+        self.stages.set_constant_buffers(
+            &mut self.device_context.borrow_mut(),
+            self.slot as u32,
+            &mut [None],
         );
+    }
+}
 
-        let initial_data = {
-            let arr = [initial_data];
-            Some(win32::D3D11SubresourceData::new(&arr, 0, 0))
+impl<T: HLSLType> ConstantBuffer<T> {
+    pub fn new_with_usage<I: Input>(
+        initial_data: T,
+        slot: usize,
+        usage: ConstantBufferUsage,
+        window: &mut crate::Window<I>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let size = hlsl_buffer_size::<T>();
+
+        let buffer_desc = match usage {
+            ConstantBufferUsage::Dynamic => win32::D3D11BufferDesc::new(
+                size as u32,
+                win32::D3D11Usage::Dynamic,
+                &[win32::D3D11BindFlag::ConstantBuffer],
+                &[win32::D3D11CPUAccessFlag::Write],
+                &[],
+                0,
+            ),
+            ConstantBufferUsage::Default => win32::D3D11BufferDesc::new(
+                size as u32,
+                win32::D3D11Usage::Default,
+                &[win32::D3D11BindFlag::ConstantBuffer],
+                &[],
+                &[],
+                0,
+            ),
         };
 
+        // The buffer may be padded past the end of T, so the initial data is read by D3D11
+        // from the padded shadow copy
+        let shadow = ConstantShadow::new(size, hlsl_bytes(&initial_data));
+
+        let initial_data = win32::D3D11SubresourceData::new(shadow.data(), 0, 0);
         let buffer = window
             .device()
-            .create_buffer(&buffer_desc, initial_data.as_ref())?;
+            .create_buffer(&buffer_desc, Some(&initial_data))?;
+
+        let device_context1 = match usage {
+            ConstantBufferUsage::Dynamic => None,
+            ConstantBufferUsage::Default => {
+                let options = window.device().check_feature_support_d3d11_options()?;
+                if options.constant_buffer_partial_update() {
+                    window
+                        .device_context()
+                        .borrow()
+                        .query_interface::<win32::ID3D11DeviceContext1>()
+                        .ok()
+                } else {
+                    None
+                }
+            }
+        };
 
         Ok(ConstantBuffer {
             constant_buffer: buffer,
+            usage,
             slot,
+            stages: ShaderStages::default(),
             phantom: PhantomData,
             device_context: window.device_context().clone(),
+            device_context1,
+            shadow,
         })
     }
 
-    fn set_data(&mut self, new_data: T) -> Result<(), Box<dyn std::error::Error>> {
-        let mut device_context = self.device_context.borrow_mut();
+    pub fn usage(&self) -> ConstantBufferUsage {
+        self.usage
+    }
+
+    // Uploads only the bytes of new_data within range, widened to whole 16 byte constants.
+    // Default buffers on D3D11.1 update just that range on the GPU, while every other buffer
+    // uploads all of its data. Skips the upload when the range is unchanged.
+    pub fn set_data_range(
+        &mut self,
+        new_data: T,
+        range: Range<usize>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let range = match self.shadow.update(hlsl_bytes(&new_data), range) {
+            Some(range) => range,
+            None => return Ok(()),
+        };
 
-        let mut mapped_resource = device_context.map(
-            &mut self.constant_buffer,
-            0,
-            win32::D3D11Map::WriteDiscard,
-            &[],
-        )?;
+        let mut device_context = self.device_context.borrow_mut();
+        match (self.usage, self.device_context1.as_mut()) {
+            (ConstantBufferUsage::Dynamic, _) => {
+                let mut mapped_resource = device_context.map(
+                    &mut self.constant_buffer,
+                    0,
+                    win32::D3D11Map::WriteDiscard,
+                    &[],
+                )?;
 
-        let data = mapped_resource.as_ref::<T>();
-        *data = new_data;
+                let shadow = self.shadow.data();
+                let base = mapped_resource.as_ref::<u8>() as *mut u8;
+                unsafe { std::ptr::copy_nonoverlapping(shadow.as_ptr(), base, shadow.len()) };
+            }
+            (ConstantBufferUsage::Default, Some(device_context1)) => {
+                device_context1.update_subresource1(
+                    &mut self.constant_buffer,
+                    0,
+                    Some(&win32::D3D11Box {
+                        left: range.start as u32,
+                        right: range.end as u32,
+                        top: 0,
+                        bottom: 1,
+                        front: 0,
+                        back: 1,
+                    }),
+                    &self.shadow.data()[range],
+                    0,
+                    0,
+                    &[],
+                );
+            }
+            // D3D11.0 can only replace a whole constant buffer
+            (ConstantBufferUsage::Default, None) => device_context.update_subresource(
+                &mut self.constant_buffer,
+                0,
+                None,
+                self.shadow.data(),
+                0,
+                0,
+            ),
+        }
 
         Ok(())
     }
 
-    fn set_slot(&mut self, slot: usize) {
-        self.slot = slot;
+    // The number of calls which uploaded data
+    pub fn upload_count(&self) -> usize {
+        self.shadow.upload_count()
     }
 
-    fn set_active(&mut self) {
-        let mut device_context = self.device_context.borrow_mut();
-        device_context
-            .vs_set_constant_buffers(self.slot as u32, &mut [Some(&mut self.constant_buffer)]);
-        device_context
-            .ps_set_constant_buffers(self.slot as u32, &mut [Some(&mut self.constant_buffer)]);
+    // The number of calls skipped because the data was unchanged
+    pub fn skipped_upload_count(&self) -> usize {
+        self.shadow.skipped_upload_count()
     }
 
-    fn clear_active(&mut self) {
-        let mut device_context = self.device_context.borrow_mut();
-        device_context.vs_set_constant_buffers(self.slot as u32, &mut [None]);
-        device_context.ps_set_constant_buffers(self.slot as u32, &mut [None]);
+    pub fn reset_upload_counts(&mut self) {
+        self.shadow.reset_counts();
+    }
+
+    pub fn stages(&self) -> ShaderStages {
+        self.stages
     }
 
-    /*
-    fn set_active_compute(&mut self) {
-        self.device_context
-            .borrow_mut()
-            .cs_set_constant_buffers(self.slot as u32, &mut [&mut self.constant_buffer]);
+    // Takes effect the next time it is set active, see ShaderStages
+    pub fn set_stages(&mut self, stages: ShaderStages) {
+        self.stages = stages;
     }
-    */
 }
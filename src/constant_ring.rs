@@ -0,0 +1,155 @@
+use crate::{hlsl_buffer_size, round_to_block, ConstantRingAllocator, HLSLType, ShaderStages};
+use alexandria_common::Input;
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+    rc::Rc,
+};
+
+const CONSTANT_SIZE: usize = 16;
+
+// A large per-frame constant buffer which hands out a block per draw. On D3D11.1 blocks are
+// bound with an offset into the one buffer, while D3D11.0 falls back to mapping a separate
+// buffer with WriteDiscard for every push.
+pub struct ConstantRingBuffer {
+    ring: Option<RingBuffer>,
+    allocator: ConstantRingAllocator,
+
+    // Fallback buffers keyed by their size
+    fallback_buffers: HashMap<usize, win32::ID3D11Buffer>,
+
+    device: Rc<win32::ID3D11Device>,
+    device_context: Rc<RefCell<win32::ID3D11DeviceContext>>,
+}
+
+struct RingBuffer {
+    buffer: win32::ID3D11Buffer,
+    device_context1: win32::ID3D11DeviceContext1,
+}
+
+fn create_constant_buffer(
+    device: &win32::ID3D11Device,
+    size: usize,
+) -> Result<win32::ID3D11Buffer, win32::DirectXError> {
+    let buffer_desc = win32::D3D11BufferDesc::new(
+        size as u32,
+        win32::D3D11Usage::Dynamic,
+        &[win32::D3D11BindFlag::ConstantBuffer],
+        &[win32::D3D11CPUAccessFlag::Write],
+        &[],
+        0,
+    );
+
+    device.create_buffer(&buffer_desc, None)
+}
+
+impl ConstantRingBuffer {
+    pub fn new<I: Input>(
+        size: usize,
+        window: &mut crate::Window<I>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let allocator = ConstantRingAllocator::new(size, crate::graphics::NUM_BUFFERS as usize);
+
+        // Offset binding and no overwrite maps of constant buffers both need D3D11.1
+        let options = window.device().check_feature_support_d3d11_options()?;
+        let ring = match window
+            .device_context()
+            .borrow()
+            .query_interface::<win32::ID3D11DeviceContext1>()
+        {
+            Ok(device_context1)
+                if options.constant_buffer_offsetting()
+                    && options.map_no_overwrite_on_dynamic_constant_buffer() =>
+            {
+                Some(RingBuffer {
+                    buffer: create_constant_buffer(window.device(), allocator.capacity())?,
+                    device_context1,
+                })
+            }
+            _ => None,
+        };
+
+        Ok(ConstantRingBuffer {
+            ring,
+            allocator,
+            fallback_buffers: HashMap::new(),
+            device: window.device().clone(),
+            device_context: window.device_context().clone(),
+        })
+    }
+
+    // Call once at the start of every frame, before pushing any constants
+    pub fn begin_frame(&mut self) {
+        self.allocator.begin_frame();
+    }
+
+    pub fn allocator(&self) -> &ConstantRingAllocator {
+        &self.allocator
+    }
+
+    pub fn offset_binding(&self) -> bool {
+        self.ring.is_some()
+    }
+
+    // Writes data into a new block and binds it to slot for the given stages. Falls back to a
+    // separate buffer when the ring is full.
+    pub fn push<T: HLSLType>(
+        &mut self,
+        data: T,
+        slot: usize,
+        stages: ShaderStages,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let size = hlsl_buffer_size::<T>();
+
+        if let Some(ring) = self.ring.as_mut() {
+            if let Some(offset) = self.allocator.allocate(size) {
+                // The GPU may still be reading the rest of the buffer, unless the ring just
+                // started over
+                let map_type = if offset == 0 {
+                    win32::D3D11Map::WriteDiscard
+                } else {
+                    win32::D3D11Map::WriteNoOverwrite
+                };
+
+                {
+                    let mut device_context = self.device_context.borrow_mut();
+                    let mut mapped_resource =
+                        device_context.map(&mut ring.buffer, 0, map_type, &[])?;
+
+                    let base = mapped_resource.as_ref::<u8>() as *mut u8;
+                    unsafe { (base.add(offset) as *mut T).write(data) };
+                }
+
+                let first_constant = (offset / CONSTANT_SIZE) as u32;
+                let num_constants = (round_to_block(size) / CONSTANT_SIZE) as u32;
+                stages.set_constant_buffers1(
+                    &mut ring.device_context1,
+                    slot as u32,
+                    &mut [Some(&mut ring.buffer)],
+                    &[first_constant],
+                    &[num_constants],
+                );
+
+                return Ok(());
+            }
+        }
+
+        let buffer = match self.fallback_buffers.entry(size) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(create_constant_buffer(&self.device, size)?),
+        };
+
+        let mut device_context = self.device_context.borrow_mut();
+        {
+            let mut mapped_resource =
+                device_context.map(buffer, 0, win32::D3D11Map::WriteDiscard, &[])?;
+            // The mapped memory is uninitialized, so it must not be dropped by an assignment
+            let base = mapped_resource.as_ref::<T>() as *mut T;
+            unsafe { base.write(data) };
+        }
+
+        stages.set_constant_buffers(&mut device_context, slot as u32, &mut [Some(buffer)]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+
+// Offset binding addresses constant buffers in blocks of 16 constants of 16 bytes each
+pub const CONSTANT_BLOCK_ALIGNMENT: usize = 256;
+
+// Sub-allocates constant blocks from a ring of bytes. Blocks are released a whole frame at a
+// time, once frames_in_flight newer frames have begun and the GPU can no longer be reading
+// them. Nothing here touches the device, the offsets map directly onto a ConstantRingBuffer.
+pub struct ConstantRingAllocator {
+    capacity: usize,
+    head: usize,
+    used: usize,
+
+    // Bytes allocated by each frame still in flight, oldest first and the current frame last
+    frames: VecDeque<usize>,
+    frames_in_flight: usize,
+}
+
+pub(crate) const fn round_to_block(size: usize) -> usize {
+    size.div_ceil(CONSTANT_BLOCK_ALIGNMENT) * CONSTANT_BLOCK_ALIGNMENT
+}
+
+impl ConstantRingAllocator {
+    // The capacity is rounded down to a whole number of blocks
+    pub fn new(capacity: usize, frames_in_flight: usize) -> Self {
+        let frames_in_flight = frames_in_flight.max(1);
+
+        let mut frames = VecDeque::with_capacity(frames_in_flight);
+        frames.push_back(0);
+
+        ConstantRingAllocator {
+            capacity: capacity / CONSTANT_BLOCK_ALIGNMENT * CONSTANT_BLOCK_ALIGNMENT,
+            head: 0,
+            used: 0,
+            frames,
+            frames_in_flight,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Bytes held by frames still in flight, including space skipped when wrapping
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames_in_flight
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frames.push_back(0);
+        while self.frames.len() > self.frames_in_flight {
+            self.used -= self.frames.pop_front().unwrap();
+        }
+    }
+
+    // Returns the offset of a new block of at least size bytes, or None if the frames in
+    // flight leave no room. A block never wraps, so the end of the ring is skipped instead.
+    pub fn allocate(&mut self, size: usize) -> Option<usize> {
+        let size = round_to_block(size.max(1));
+
+        if self.used == 0 {
+            self.head = 0;
+        }
+
+        let (offset, skipped) = if self.head + size > self.capacity {
+            (0, self.capacity - self.head)
+        } else {
+            (self.head, 0)
+        };
+
+        if self.used + skipped + size > self.capacity {
+            return None;
+        }
+
+        self.head = offset + size;
+        self.used += skipped + size;
+        *self.frames.back_mut().unwrap() += skipped + size;
+
+        Some(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_are_aligned() {
+        let mut allocator = ConstantRingAllocator::new(4096, 2);
+        assert_eq!(allocator.allocate(1), Some(0));
+        assert_eq!(allocator.allocate(0), Some(256));
+        assert_eq!(allocator.allocate(256), Some(512));
+        assert_eq!(allocator.allocate(257), Some(768));
+        assert_eq!(allocator.allocate(64), Some(1280));
+        assert_eq!(allocator.used(), 1536);
+
+        // The capacity is rounded down to whole blocks
+        assert_eq!(ConstantRingAllocator::new(1000, 2).capacity(), 768);
+    }
+
+    #[test]
+    fn wraps_around() {
+        let mut allocator = ConstantRingAllocator::new(1024, 2);
+        assert_eq!(allocator.allocate(512), Some(0));
+
+        allocator.begin_frame();
+        assert_eq!(allocator.allocate(256), Some(512));
+
+        // Releases the first frame, leaving room at the start but not at the end
+        allocator.begin_frame();
+        assert_eq!(allocator.used(), 256);
+        assert_eq!(allocator.allocate(512), Some(0));
+
+        // The skipped end of the ring counts as used until its frame is released
+        assert_eq!(allocator.used(), 1024);
+        assert_eq!(allocator.allocate(1), None);
+
+        allocator.begin_frame();
+        assert_eq!(allocator.used(), 768);
+        assert_eq!(allocator.allocate(256), Some(512));
+    }
+
+    #[test]
+    fn larger_than_capacity() {
+        let mut allocator = ConstantRingAllocator::new(1024, 2);
+        assert_eq!(allocator.allocate(1025), None);
+        assert_eq!(allocator.used(), 0);
+        assert_eq!(allocator.allocate(1024), Some(0));
+        assert_eq!(allocator.allocate(1), None);
+
+        assert_eq!(ConstantRingAllocator::new(0, 2).allocate(1), None);
+    }
+
+    #[test]
+    fn frames_are_released_once_out_of_flight() {
+        let mut allocator = ConstantRingAllocator::new(2048, 3);
+        allocator.allocate(512);
+        allocator.begin_frame();
+        allocator.allocate(512);
+        allocator.begin_frame();
+        assert_eq!(allocator.used(), 1024);
+
+        allocator.begin_frame();
+        assert_eq!(allocator.used(), 512);
+        allocator.begin_frame();
+        assert_eq!(allocator.used(), 0);
+
+        // An empty ring starts over from the beginning
+        assert_eq!(allocator.allocate(256), Some(0));
+
+        // A single frame in flight releases everything each frame
+        let mut allocator = ConstantRingAllocator::new(1024, 0);
+        assert_eq!(allocator.frames_in_flight(), 1);
+        assert_eq!(allocator.allocate(1024), Some(0));
+        allocator.begin_frame();
+        assert_eq!(allocator.used(), 0);
+        assert_eq!(allocator.allocate(1024), Some(0));
+    }
+}
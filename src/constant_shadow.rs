@@ -0,0 +1,127 @@
+use std::ops::Range;
+
+const CONSTANT_SIZE: usize = 16;
+
+// A copy of what was last uploaded to a constant buffer, padded to the size of the buffer. It
+// decides which bytes an upload has to send, so it is kept apart from the device.
+pub(crate) struct ConstantShadow {
+    data: Vec<u8>,
+    upload_count: usize,
+    skipped_upload_count: usize,
+}
+
+impl ConstantShadow {
+    // The size must be a whole number of 16 byte constants and at least as large as initial
+    pub(crate) fn new(size: usize, initial: &[u8]) -> Self {
+        let mut data = vec![0; size];
+        data[..initial.len()].copy_from_slice(initial);
+
+        ConstantShadow {
+            data,
+            upload_count: 0,
+            skipped_upload_count: 0,
+        }
+    }
+
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    // Copies the bytes within range, clamped to bytes, into the shadow and returns that range
+    // widened to whole constants. Returns None when the bytes are unchanged, so nothing needs
+    // to be uploaded.
+    pub(crate) fn update(&mut self, bytes: &[u8], range: Range<usize>) -> Option<Range<usize>> {
+        let end = range.end.min(bytes.len());
+        let start = range.start.min(end);
+
+        if self.data[start..end] == bytes[start..end] {
+            self.skipped_upload_count += 1;
+            return None;
+        }
+
+        self.data[start..end].copy_from_slice(&bytes[start..end]);
+        self.upload_count += 1;
+
+        Some(start / CONSTANT_SIZE * CONSTANT_SIZE..end.div_ceil(CONSTANT_SIZE) * CONSTANT_SIZE)
+    }
+
+    pub(crate) fn upload_count(&self) -> usize {
+        self.upload_count
+    }
+
+    pub(crate) fn skipped_upload_count(&self) -> usize {
+        self.skipped_upload_count
+    }
+
+    pub(crate) fn reset_counts(&mut self) {
+        self.upload_count = 0;
+        self.skipped_upload_count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_data_is_skipped() {
+        let initial = bytes(&[1.0, 2.0, 3.0]);
+        let mut shadow = ConstantShadow::new(16, &initial);
+        assert_eq!(shadow.data().len(), 16);
+
+        assert_eq!(shadow.update(&initial, 0..initial.len()), None);
+        assert_eq!(shadow.skipped_upload_count(), 1);
+        assert_eq!(shadow.upload_count(), 0);
+
+        let changed = bytes(&[1.0, 2.0, 4.0]);
+        assert_eq!(shadow.update(&changed, 0..changed.len()), Some(0..16));
+        assert_eq!(shadow.update(&changed, 0..changed.len()), None);
+        assert_eq!(&shadow.data()[..12], changed.as_slice());
+        assert_eq!(shadow.upload_count(), 1);
+        assert_eq!(shadow.skipped_upload_count(), 2);
+
+        shadow.reset_counts();
+        assert_eq!(shadow.upload_count(), 0);
+        assert_eq!(shadow.skipped_upload_count(), 0);
+    }
+
+    #[test]
+    fn ranges_widen_to_whole_constants() {
+        let initial = bytes(&[0.0; 12]);
+        let mut shadow = ConstantShadow::new(48, &initial);
+
+        // The fifth float lives in the second constant
+        let mut changed = bytes(&[0.0; 12]);
+        changed[16..20].copy_from_slice(&5.0f32.to_le_bytes());
+        assert_eq!(shadow.update(&changed, 16..20), Some(16..32));
+
+        // Changes outside of the range are neither compared nor copied
+        changed[0..4].copy_from_slice(&1.0f32.to_le_bytes());
+        assert_eq!(shadow.update(&changed, 16..20), None);
+        assert_eq!(&shadow.data()[0..4], &0.0f32.to_le_bytes());
+
+        changed[40..44].copy_from_slice(&2.0f32.to_le_bytes());
+        assert_eq!(shadow.update(&changed, 4..44), Some(0..48));
+        assert_eq!(&shadow.data()[4..], &changed[4..]);
+        assert_eq!(&shadow.data()[0..4], &0.0f32.to_le_bytes());
+    }
+
+    #[test]
+    fn ranges_are_clamped_to_the_data() {
+        // A 20 byte type in a 32 byte buffer
+        let initial = bytes(&[0.0; 5]);
+        let mut shadow = ConstantShadow::new(32, &initial);
+
+        let changed = bytes(&[0.0, 0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(shadow.update(&changed, 8..100), Some(0..32));
+        assert_eq!(shadow.update(&changed, 50..100), None);
+        assert_eq!(&shadow.data()[20..], &[0; 12]);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::{BoundingBox, BoundingSphere, LHRowMajorMatrix};
+use alexandria_common::{Matrix, Vector3};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Containment {
+    Outside,
+    Intersecting,
+    Inside,
+}
+
+// A plane where normal . point + distance == 0, with the normal facing into the frustum
+#[derive(Debug, Clone, Copy)]
+pub struct Plane {
+    normal: Vector3,
+    distance: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    planes: [Plane; 6],
+}
+
+impl Plane {
+    pub fn new(normal: Vector3, distance: f32) -> Self {
+        Plane { normal, distance }
+    }
+
+    fn from_coefficients(a: f32, b: f32, c: f32, d: f32) -> Self {
+        let length = (a * a + b * b + c * c).sqrt();
+
+        // Planes at infinity have no normal, so leave them as they are
+        if length == 0.0 {
+            return Plane::new(Vector3::new(a, b, c), d);
+        }
+
+        Plane::new(Vector3::new(a / length, b / length, c / length), d / length)
+    }
+
+    pub fn normal(&self) -> Vector3 {
+        self.normal
+    }
+
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    pub fn signed_distance(&self, point: Vector3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+impl Frustum {
+    // Works for any projection that maps the visible volume to -w <= x, y <= w and 0 <= z <= w,
+    // including the reversed-Z and infinite projections
+    pub fn new(view_projection: &LHRowMajorMatrix) -> Self {
+        let row = |i| {
+            [
+                view_projection.get(i, 0),
+                view_projection.get(i, 1),
+                view_projection.get(i, 2),
+                view_projection.get(i, 3),
+            ]
+        };
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+
+        let plane = |a: [f32; 4], b: [f32; 4], sign: f32| {
+            Plane::from_coefficients(
+                a[0] + sign * b[0],
+                a[1] + sign * b[1],
+                a[2] + sign * b[2],
+                a[3] + sign * b[3],
+            )
+        };
+
+        Frustum {
+            planes: [
+                plane(r3, r0, 1.0),
+                plane(r3, r0, -1.0),
+                plane(r3, r1, 1.0),
+                plane(r3, r1, -1.0),
+                Plane::from_coefficients(r2[0], r2[1], r2[2], r2[3]),
+                plane(r3, r2, -1.0),
+            ],
+        }
+    }
+
+    // Ordered left, right, bottom, top, z = 0, z = w. That is near then far for a regular
+    // projection, but far then near for a reversed-Z one
+    pub fn planes(&self) -> &[Plane; 6] {
+        &self.planes
+    }
+
+    pub fn contains_point(&self, point: Vector3) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    pub fn contains_sphere(&self, sphere: &BoundingSphere) -> Containment {
+        let mut containment = Containment::Inside;
+        for plane in &self.planes {
+            let distance = plane.signed_distance(sphere.center());
+            if distance < -sphere.radius() {
+                return Containment::Outside;
+            } else if distance < sphere.radius() {
+                containment = Containment::Intersecting;
+            }
+        }
+
+        containment
+    }
+
+    pub fn contains_box(&self, bounding_box: &BoundingBox) -> Containment {
+        let min = bounding_box.min();
+        let max = bounding_box.max();
+
+        let mut containment = Containment::Inside;
+        for plane in &self.planes {
+            let normal = plane.normal();
+
+            // The corners furthest along and against the plane normal
+            let positive = Vector3::new(
+                if normal.x() >= 0.0 { max.x() } else { min.x() },
+                if normal.y() >= 0.0 { max.y() } else { min.y() },
+                if normal.z() >= 0.0 { max.z() } else { min.z() },
+            );
+            let negative = Vector3::new(
+                if normal.x() >= 0.0 { min.x() } else { max.x() },
+                if normal.y() >= 0.0 { min.y() } else { max.y() },
+                if normal.z() >= 0.0 { min.z() } else { max.z() },
+            );
+
+            if plane.signed_distance(positive) < 0.0 {
+                return Containment::Outside;
+            } else if plane.signed_distance(negative) < 0.0 {
+                containment = Containment::Intersecting;
+            }
+        }
+
+        containment
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.contains_sphere(sphere) != Containment::Outside
+    }
+
+    pub fn intersects_box(&self, bounding_box: &BoundingBox) -> bool {
+        self.contains_box(bounding_box) != Containment::Outside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+
+    // Camera at z = -10 looking down +z, with a 90 degree field of view, so the visible volume
+    // is |x|, |y| <= z + 10 for -9 <= z <= 90
+    fn view_projection(projection: LHRowMajorMatrix) -> LHRowMajorMatrix {
+        let view = LHRowMajorMatrix::look_at(
+            Vector3::new(0.0, 0.0, -10.0),
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        projection * view
+    }
+
+    fn frustum() -> Frustum {
+        Frustum::new(&view_projection(LHRowMajorMatrix::perspective(
+            FRAC_PI_2, 1.0, 1.0, 100.0,
+        )))
+    }
+
+    #[test]
+    fn points() {
+        let frustum = frustum();
+        assert!(frustum.contains_point(Vector3::new(0.0, 0.0, 0.0)));
+        assert!(frustum.contains_point(Vector3::new(9.5, -9.5, 0.0)));
+        assert!(frustum.contains_point(Vector3::new(0.0, 0.0, 89.0)));
+        assert!(!frustum.contains_point(Vector3::new(0.0, 0.0, -9.5)));
+        assert!(!frustum.contains_point(Vector3::new(0.0, 0.0, 95.0)));
+        assert!(!frustum.contains_point(Vector3::new(11.0, 0.0, 0.0)));
+        assert!(!frustum.contains_point(Vector3::new(0.0, -11.0, 0.0)));
+    }
+
+    #[test]
+    fn spheres() {
+        let frustum = frustum();
+        let sphere = |x, y, z, radius| BoundingSphere::new(Vector3::new(x, y, z), radius);
+
+        assert_eq!(
+            frustum.contains_sphere(&sphere(0.0, 0.0, 0.0, 1.0)),
+            Containment::Inside
+        );
+        assert_eq!(
+            frustum.contains_sphere(&sphere(10.0, 0.0, 0.0, 1.0)),
+            Containment::Intersecting
+        );
+        assert_eq!(
+            frustum.contains_sphere(&sphere(0.0, 0.0, 90.0, 1.0)),
+            Containment::Intersecting
+        );
+        assert_eq!(
+            frustum.contains_sphere(&sphere(30.0, 0.0, 0.0, 1.0)),
+            Containment::Outside
+        );
+        assert_eq!(
+            frustum.contains_sphere(&sphere(0.0, 0.0, -12.0, 1.0)),
+            Containment::Outside
+        );
+        assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, 200.0, 1.0)));
+    }
+
+    #[test]
+    fn boxes() {
+        let frustum = frustum();
+        let cube = |x, y, z, extent| {
+            BoundingBox::from_center_extents(
+                Vector3::new(x, y, z),
+                Vector3::new(extent, extent, extent),
+            )
+        };
+
+        assert_eq!(
+            frustum.contains_box(&cube(0.0, 0.0, 0.0, 1.0)),
+            Containment::Inside
+        );
+        assert_eq!(
+            frustum.contains_box(&cube(0.0, 0.0, -9.0, 0.5)),
+            Containment::Intersecting
+        );
+        assert_eq!(
+            frustum.contains_box(&cube(0.0, 10.0, 0.0, 1.0)),
+            Containment::Intersecting
+        );
+        assert_eq!(
+            frustum.contains_box(&cube(0.0, 0.0, 200.0, 1.0)),
+            Containment::Outside
+        );
+        assert_eq!(
+            frustum.contains_box(&cube(-30.0, 0.0, 0.0, 1.0)),
+            Containment::Outside
+        );
+        assert!(frustum.intersects_box(&cube(0.0, 0.0, 90.0, 1.0)));
+    }
+
+    #[test]
+    fn plane_order() {
+        let near = Vector3::new(0.0, 0.0, -9.0);
+        let far = Vector3::new(0.0, 0.0, 90.0);
+
+        let planes = *frustum().planes();
+        assert!(planes[4].signed_distance(near).abs() < 1e-4);
+        assert!(planes[5].signed_distance(far).abs() < 1e-3);
+
+        // Reversed-Z swaps the near and far planes
+        let reversed = Frustum::new(&view_projection(LHRowMajorMatrix::perspective_reversed_z(
+            FRAC_PI_2, 1.0, 1.0, 100.0,
+        )));
+        let planes = reversed.planes();
+        assert!(planes[4].signed_distance(far).abs() < 1e-3);
+        assert!(planes[5].signed_distance(near).abs() < 1e-4);
+        assert!(reversed.contains_point(Vector3::new(0.0, 0.0, 0.0)));
+        assert!(!reversed.contains_point(Vector3::new(0.0, 0.0, -9.5)));
+        assert!(!reversed.contains_point(Vector3::new(0.0, 0.0, 95.0)));
+    }
+
+    #[test]
+    fn infinite_far_plane() {
+        let frustum = Frustum::new(&view_projection(LHRowMajorMatrix::perspective_infinite(
+            FRAC_PI_2, 1.0, 1.0,
+        )));
+        assert!(frustum.contains_point(Vector3::new(0.0, 0.0, 1.0e6)));
+        assert!(!frustum.contains_point(Vector3::new(0.0, 0.0, -9.5)));
+    }
+}
@@ -19,6 +19,12 @@ pub enum GraphicsCreationErrorClass {
     InfoQueue,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthMode {
+    Standard,
+    ReversedZ,
+}
+
 #[allow(unused)]
 pub struct Graphics {
     swap_chain: win32::IDXGISwapChain,
@@ -30,6 +36,7 @@ pub struct Graphics {
     depth_stencil_view: Option<win32::ID3D11DepthStencilView>,
     rasterizer_state: win32::ID3D11RasterizerState,
     blend_state: win32::ID3D11BlendState,
+    depth_mode: DepthMode,
     rendering: bool,
 
     viewports: Vec<Viewport>,
@@ -49,7 +56,7 @@ pub struct GraphicsCreationError {
 #[derive(Debug)]
 pub struct RenderError(win32::DirectXError);
 
-const NUM_BUFFERS: u32 = 3;
+pub(crate) const NUM_BUFFERS: u32 = 3;
 
 fn get_refresh_rate(width: u32, height: u32) -> Result<(u32, u32), GraphicsCreationError> {
     // Create a factory
@@ -137,13 +144,14 @@ fn create_depth_stencil_view(
     device: &win32::ID3D11Device,
     width: u32,
     height: u32,
+    depth_mode: DepthMode,
 ) -> Result<(win32::ID3D11Texture2D, win32::ID3D11DepthStencilView), GraphicsCreationError> {
     let depth_buffer_desc = win32::D3D11Texture2DDesc::new(
         width,
         height,
         1,
         1,
-        win32::DXGIFormat::D24UnormS8Uint,
+        depth_mode.format(),
         1,
         0,
         win32::D3D11Usage::Default,
@@ -163,7 +171,7 @@ fn create_depth_stencil_view(
 
     // Create depth stencil view
     let depth_stencil_view_desc = win32::D3D11DepthStencilViewDesc::new(
-        win32::DXGIFormat::D24UnormS8Uint,
+        depth_mode.format(),
         win32::D3D11DSVDimension::Texture2D,
         &[],
     );
@@ -178,6 +186,58 @@ fn create_depth_stencil_view(
     }
 }
 
+fn create_depth_stencil_state(
+    device: &win32::ID3D11Device,
+    depth_mode: DepthMode,
+) -> Result<win32::ID3D11DepthStencilState, GraphicsCreationError> {
+    let depth_stencil_desc = win32::D3D11DepthStencilDesc::new(
+        true,
+        win32::D3D11DepthWriteMask::All,
+        depth_mode.comparison_func(),
+        true,
+        0xFF,
+        0xFF,
+        win32::D3D11StencilOp::Keep,
+        win32::D3D11StencilOp::Incr,
+        win32::D3D11StencilOp::Keep,
+        win32::D3D11ComparisonFunc::Always,
+        win32::D3D11StencilOp::Keep,
+        win32::D3D11StencilOp::Decr,
+        win32::D3D11StencilOp::Keep,
+        win32::D3D11ComparisonFunc::Always,
+    );
+    match device.create_depth_stencil_state(&depth_stencil_desc) {
+        Ok(depth_stencil_state) => Ok(depth_stencil_state),
+        Err(error) => Err(GraphicsCreationError::new(
+            GraphicsCreationErrorClass::DepthStencilState,
+            error,
+        )),
+    }
+}
+
+impl DepthMode {
+    fn format(&self) -> win32::DXGIFormat {
+        match self {
+            DepthMode::Standard => win32::DXGIFormat::D24UnormS8Uint,
+            DepthMode::ReversedZ => win32::DXGIFormat::D32FloatS8X24Uint,
+        }
+    }
+
+    fn comparison_func(&self) -> win32::D3D11ComparisonFunc {
+        match self {
+            DepthMode::Standard => win32::D3D11ComparisonFunc::Less,
+            DepthMode::ReversedZ => win32::D3D11ComparisonFunc::Greater,
+        }
+    }
+
+    fn clear_depth(&self) -> f32 {
+        match self {
+            DepthMode::Standard => 1.0,
+            DepthMode::ReversedZ => 0.0,
+        }
+    }
+}
+
 impl Graphics {
     pub fn new(
         handle: win32::HWnd,
@@ -236,35 +296,12 @@ impl Graphics {
         let render_target_view = create_render_target_view(&device, &mut swap_chain)?;
 
         // Create depth stencil buffer and view
+        let depth_mode = DepthMode::Standard;
         let (depth_stencil_buffer, depth_stencil_view) =
-            create_depth_stencil_view(&device, width, height)?;
+            create_depth_stencil_view(&device, width, height, depth_mode)?;
 
         // Create a depth stencil
-        let depth_stencil_desc = win32::D3D11DepthStencilDesc::new(
-            true,
-            win32::D3D11DepthWriteMask::All,
-            win32::D3D11ComparisonFunc::Less,
-            true,
-            0xFF,
-            0xFF,
-            win32::D3D11StencilOp::Keep,
-            win32::D3D11StencilOp::Incr,
-            win32::D3D11StencilOp::Keep,
-            win32::D3D11ComparisonFunc::Always,
-            win32::D3D11StencilOp::Keep,
-            win32::D3D11StencilOp::Decr,
-            win32::D3D11StencilOp::Keep,
-            win32::D3D11ComparisonFunc::Always,
-        );
-        let mut depth_stencil_state = match device.create_depth_stencil_state(&depth_stencil_desc) {
-            Ok(depth_stencil_state) => depth_stencil_state,
-            Err(error) => {
-                return Err(GraphicsCreationError::new(
-                    GraphicsCreationErrorClass::DepthStencilState,
-                    error,
-                ))
-            }
-        };
+        let mut depth_stencil_state = create_depth_stencil_state(&device, depth_mode)?;
 
         // Set depth stencil state
         device_context.om_set_depth_stencil_state(&mut depth_stencil_state, 1);
@@ -351,6 +388,7 @@ impl Graphics {
             depth_stencil_view: Some(depth_stencil_view),
             rasterizer_state,
             blend_state,
+            depth_mode,
             rendering: false,
             #[cfg(debug_assertions)]
             info_queue,
@@ -376,7 +414,7 @@ impl Graphics {
         device_context.clear_depth_stencil_view(
             self.depth_stencil_view.as_mut().unwrap(),
             &[win32::D3D11ClearFlag::Depth],
-            1.0,
+            self.depth_mode.clear_depth(),
             0,
         );
         device_context.om_set_render_targets(
@@ -437,7 +475,7 @@ impl Graphics {
         self.render_target_view =
             Some(create_render_target_view(&self.device, &mut self.swap_chain).unwrap());
         (self.depth_stencil_buffer, self.depth_stencil_view) =
-            create_depth_stencil_view(&self.device, width, height)
+            create_depth_stencil_view(&self.device, width, height, self.depth_mode)
                 .map(|(dsb, dsv)| (dsb, Some(dsv)))
                 .unwrap();
 
@@ -446,6 +484,34 @@ impl Graphics {
         device_context.rs_set_viewports(&[&viewport]);
     }
 
+    pub(crate) fn set_depth_mode(
+        &mut self,
+        depth_mode: DepthMode,
+        width: u32,
+        height: u32,
+    ) -> Result<(), GraphicsCreationError> {
+        let mut device_context = self.device_context.borrow_mut();
+
+        // Clear render targets and release the old Depth/Stencil view
+        device_context.om_set_render_targets(&mut [None], None);
+        drop(self.depth_stencil_view.take());
+
+        // Create the new Depth/Stencil buffer, view and state
+        let (depth_stencil_buffer, depth_stencil_view) =
+            create_depth_stencil_view(&self.device, width, height, depth_mode)?;
+        self.depth_stencil_buffer = depth_stencil_buffer;
+        self.depth_stencil_view = Some(depth_stencil_view);
+        self.depth_stencil_state = create_depth_stencil_state(&self.device, depth_mode)?;
+        device_context.om_set_depth_stencil_state(&mut self.depth_stencil_state, 1);
+
+        self.depth_mode = depth_mode;
+        Ok(())
+    }
+
+    pub fn depth_mode(&self) -> DepthMode {
+        self.depth_mode
+    }
+
     pub fn create_viewport(
         &mut self,
         top_left: alexandria_common::Vector2,
@@ -0,0 +1,464 @@
+use crate::{HLSLMatrix, LHRowMajorMatrix, Matrix3, PaddedMatrix3, Quaternion};
+use alexandria_common::{Vector2, Vector3, Vector4};
+use std::mem::{align_of, size_of};
+
+const REGISTER_SIZE: usize = 16;
+
+// D3D11 limits the stride of a structured buffer to 2048 bytes
+const MAX_STRUCTURED_STRIDE: usize = 2048;
+
+// How HLSL places a member inside a cbuffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HLSLPacking {
+    // Scalars and vectors, which share a register with the previous member unless they would
+    // cross into the next one
+    Packed,
+    // Matrices, arrays and structs, which always start a new register
+    Register,
+    // Explicit padding, which is never read by the shader
+    Padding,
+}
+
+// A type which can be written directly into a constant buffer. Implementing it is unsafe, as
+// the memory layout of the type must match what HLSL expects and it must not contain any
+// implicit padding, so every byte of it is initialized. Prefer hlsl_struct!, which checks
+// every member at compile time, over implementing it by hand.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe trait HLSLType: Sized {
+    const PACKING: HLSLPacking;
+
+    // The number of bytes HLSL reads for this type, which may be less than its Rust size when
+    // the type ends with padding HLSL is allowed to pack the next member into
+    const SIZE: usize = size_of::<Self>();
+}
+
+// A type which can be an element of a structured buffer. Unlike a cbuffer, a structured buffer
+// is tightly packed, with every member following the previous one and no registers. The same
+// safety requirements as HLSLType apply, checked by hlsl_structured_struct!.
+#[allow(clippy::missing_safety_doc)]
+pub unsafe trait HLSLStructuredType: Copy {}
+
+// Explicit padding of N 4 byte words, for lining up the next member of an hlsl_struct!
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub struct HLSLPadding<const N: usize>([u32; N]);
+
+// Declares a #[repr(C)] struct and checks at compile time that every member sits at the same
+// offset HLSL would give it in a cbuffer. A member HLSL would move to the next register needs
+// HLSLPadding inserted before it, and one HLSL would pack into the end of a float3x3 needs the
+// members reordered.
+///
+/// ```
+/// use alexandria_common::{Vector2, Vector3};
+/// use alexandria_dx11::{hlsl_struct, HLSLPadding, LHRowMajorMatrix};
+///
+/// hlsl_struct! {
+///     pub struct Constants {
+///         pub world: LHRowMajorMatrix,
+///         pub color: Vector3,
+///         pub intensity: f32,
+///         pub offset: Vector2,
+///         pub padding: HLSLPadding<2>,
+///     }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use alexandria_common::{Vector2, Vector3};
+/// use alexandria_dx11::hlsl_struct;
+///
+/// // HLSL moves offset to the next register
+/// hlsl_struct! {
+///     struct Constants {
+///         color: Vector3,
+///         offset: Vector2,
+///     }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use alexandria_dx11::{hlsl_struct, PaddedMatrix3};
+///
+/// // HLSL packs scale into the end of the last row, where Rust cannot place it
+/// hlsl_struct! {
+///     struct Constants {
+///         normal: PaddedMatrix3,
+///         scale: f32,
+///     }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use alexandria_dx11::hlsl_struct;
+///
+/// // Every array element starts a new register
+/// hlsl_struct! {
+///     struct Constants {
+///         weights: [f32; 4],
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! hlsl_struct {
+    (
+        $(#[$attr: meta])*
+        $vis: vis struct $name: ident {
+            $($(#[$field_attr: meta])* $field_vis: vis $field: ident: $ty: ty),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[repr(C)]
+        $vis struct $name {
+            $($(#[$field_attr])* $field_vis $field: $ty),*
+        }
+
+        unsafe impl $crate::HLSLType for $name {
+            const PACKING: $crate::HLSLPacking = $crate::HLSLPacking::Register;
+
+            #[allow(unused_mut)]
+            const SIZE: usize = {
+                let mut end = 0;
+                $(
+                    let offset = $crate::hlsl_offset(
+                        end,
+                        <$ty as $crate::HLSLType>::SIZE,
+                        <$ty as $crate::HLSLType>::PACKING,
+                    );
+                    assert!(
+                        offset >= ::std::mem::offset_of!($name, $field),
+                        concat!(
+                            "`",
+                            stringify!($name),
+                            "::",
+                            stringify!($field),
+                            "` is packed by HLSL into the end of the previous member, which Rust ",
+                            "cannot match, reorder the members"
+                        ),
+                    );
+                    assert!(
+                        offset == ::std::mem::offset_of!($name, $field),
+                        concat!(
+                            "`",
+                            stringify!($name),
+                            "::",
+                            stringify!($field),
+                            "` is not where HLSL places it, insert HLSLPadding before it"
+                        ),
+                    );
+                    end = offset + <$ty as $crate::HLSLType>::SIZE;
+                )*
+
+                assert!(
+                    0 $(+ ::std::mem::size_of::<$ty>())* == ::std::mem::size_of::<$name>(),
+                    concat!(
+                        "`",
+                        stringify!($name),
+                        "` contains implicit padding, replace it with HLSLPadding"
+                    ),
+                );
+
+                end
+            };
+        }
+
+        const _: usize = <$name as $crate::HLSLType>::SIZE;
+    };
+}
+
+// Declares a #[repr(C)] struct for structured buffers, which must also derive Copy, and checks
+// at compile time that it is tightly packed, like HLSL lays out the elements of a structured buffer. Members follow
+// each other without padding, so a Vector3 followed by a Vector2 is 20 bytes.
+#[macro_export]
+macro_rules! hlsl_structured_struct {
+    (
+        $(#[$attr: meta])*
+        $vis: vis struct $name: ident {
+            $($(#[$field_attr: meta])* $field_vis: vis $field: ident: $ty: ty),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[repr(C)]
+        $vis struct $name {
+            $($(#[$field_attr])* $field_vis $field: $ty),*
+        }
+
+        unsafe impl $crate::HLSLStructuredType for $name {}
+
+        const _: () = {
+            const fn member<T: $crate::HLSLStructuredType>() -> usize {
+                ::std::mem::size_of::<T>()
+            }
+
+            assert!(
+                0 $(+ member::<$ty>())* == ::std::mem::size_of::<$name>(),
+                concat!(
+                    "`",
+                    stringify!($name),
+                    "` contains implicit padding, which HLSL does not add to a structured buffer"
+                ),
+            );
+        };
+    };
+}
+
+// Returns where HLSL places a member of the given size and packing which follows a member
+// ending at end
+pub const fn hlsl_offset(end: usize, size: usize, packing: HLSLPacking) -> usize {
+    match packing {
+        HLSLPacking::Packed => {
+            if end % REGISTER_SIZE + size > REGISTER_SIZE {
+                round_to_register(end)
+            } else {
+                end
+            }
+        }
+        HLSLPacking::Register => round_to_register(end),
+        HLSLPacking::Padding => end,
+    }
+}
+
+// The size of a constant buffer holding T, which D3D11 requires to be a multiple of 16 bytes
+pub(crate) const fn hlsl_buffer_size<T: HLSLType>() -> usize {
+    // Evaluating the packing rejects arrays with the wrong element size
+    let _ = T::PACKING;
+
+    match size_of::<T>() {
+        0 => REGISTER_SIZE,
+        size => round_to_register(size),
+    }
+}
+
+// The stride of a structured buffer of T, which D3D11 requires to be a non-zero multiple of 4
+// bytes up to 2048 bytes
+pub(crate) const fn structured_stride<T: HLSLStructuredType>() -> usize {
+    assert!(
+        size_of::<T>() > 0 && size_of::<T>().is_multiple_of(4),
+        "The stride of a structured buffer must be a non-zero multiple of 4 bytes"
+    );
+    assert!(
+        size_of::<T>() <= MAX_STRUCTURED_STRIDE,
+        "The stride of a structured buffer must be at most 2048 bytes"
+    );
+    size_of::<T>()
+}
+
+// HLSLType rules out implicit padding, so every byte of data is initialized
+pub(crate) fn hlsl_bytes<T: HLSLType>(data: &T) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(data as *const T as *const u8, size_of::<T>()) }
+}
+
+const fn round_to_register(offset: usize) -> usize {
+    offset.div_ceil(REGISTER_SIZE) * REGISTER_SIZE
+}
+
+impl<const N: usize> HLSLPadding<N> {
+    pub const fn new() -> Self {
+        HLSLPadding([0; N])
+    }
+}
+
+impl<const N: usize> Default for HLSLPadding<N> {
+    fn default() -> Self {
+        HLSLPadding::new()
+    }
+}
+
+unsafe impl<const N: usize> HLSLType for HLSLPadding<N> {
+    const PACKING: HLSLPacking = HLSLPacking::Padding;
+}
+
+// Every element of an HLSL array starts a new register, so the element type must already be
+// padded out to a whole number of registers for the Rust array to match
+unsafe impl<T: HLSLType, const N: usize> HLSLType for [T; N] {
+    const PACKING: HLSLPacking = {
+        assert!(
+            size_of::<T>() == round_to_register(T::SIZE),
+            "HLSL array elements start on a new register, pad the element type to a multiple of 16 bytes"
+        );
+        HLSLPacking::Register
+    };
+
+    const SIZE: usize = if N == 0 {
+        0
+    } else {
+        (N - 1) * size_of::<T>() + T::SIZE
+    };
+}
+
+macro_rules! impl_hlsl_type {
+    ($packing: ident, $($name: ty),*) => {
+        $(
+            unsafe impl HLSLType for $name {
+                const PACKING: HLSLPacking = HLSLPacking::$packing;
+            }
+        )*
+    };
+}
+
+// The vectors come from alexandria_common, so make sure they are still plain f32s
+const _: () = {
+    assert!(size_of::<Vector2>() == 2 * 4 && align_of::<Vector2>() == 4);
+    assert!(size_of::<Vector3>() == 3 * 4 && align_of::<Vector3>() == 4);
+    assert!(size_of::<Vector4>() == 4 * 4 && align_of::<Vector4>() == 4);
+};
+
+impl_hlsl_type!(Packed, f32, i32, u32, Vector2, Vector3, Vector4, Quaternion);
+// The other matrix variants lay out their memory differently, so they convert to HLSLMatrix
+// to be uploaded rather than risk a shader silently reading the transpose
+impl_hlsl_type!(Register, LHRowMajorMatrix, HLSLMatrix);
+
+// Structured buffers have no registers, so HLSL arrays are as tightly packed as Rust ones
+unsafe impl<T: HLSLStructuredType, const N: usize> HLSLStructuredType for [T; N] {}
+
+macro_rules! impl_hlsl_structured_type {
+    ($($name: ty),*) => {
+        $(
+            unsafe impl HLSLStructuredType for $name {}
+        )*
+    };
+}
+
+impl_hlsl_structured_type!(
+    f32,
+    i32,
+    u32,
+    Vector2,
+    Vector3,
+    Vector4,
+    Quaternion,
+    Matrix3,
+    LHRowMajorMatrix,
+    HLSLMatrix
+);
+
+// HLSL packs the next member into the unused end of the last row
+unsafe impl HLSLType for PaddedMatrix3 {
+    const PACKING: HLSLPacking = HLSLPacking::Register;
+    const SIZE: usize = 3 * 4 * 4 - 4;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::offset_of;
+
+    crate::hlsl_struct! {
+        struct Scalars {
+            a: f32,
+            b: Vector2,
+            c: u32,
+            d: Vector3,
+            e: i32,
+        }
+    }
+
+    crate::hlsl_struct! {
+        struct Padded {
+            color: Vector3,
+            padding: HLSLPadding<1>,
+            offset: Vector2,
+            padding2: HLSLPadding<2>,
+            world: LHRowMajorMatrix,
+            normal: PaddedMatrix3,
+            scale: Vector4,
+        }
+    }
+
+    crate::hlsl_struct! {
+        struct Element {
+            position: Vector3,
+            radius: f32,
+        }
+    }
+
+    crate::hlsl_struct! {
+        struct Nested {
+            elements: [Element; 3],
+            count: u32,
+            tail: Vector2,
+        }
+    }
+
+    crate::hlsl_structured_struct! {
+        #[derive(Clone, Copy)]
+        struct Particle {
+            position: Vector3,
+            uv: Vector2,
+        }
+    }
+
+    crate::hlsl_structured_struct! {
+        #[derive(Clone, Copy)]
+        struct Instance {
+            world: LHRowMajorMatrix,
+            normal: Matrix3,
+            particles: [Particle; 2],
+            id: u32,
+        }
+    }
+
+    #[test]
+    fn packed_members_share_registers() {
+        assert_eq!(offset_of!(Scalars, b), 4);
+        assert_eq!(offset_of!(Scalars, c), 12);
+        assert_eq!(offset_of!(Scalars, d), 16);
+        assert_eq!(offset_of!(Scalars, e), 28);
+        assert_eq!(Scalars::SIZE, 32);
+    }
+
+    #[test]
+    fn padding_lines_members_up() {
+        assert_eq!(offset_of!(Padded, offset), 16);
+        assert_eq!(offset_of!(Padded, world), 32);
+        assert_eq!(offset_of!(Padded, normal), 96);
+        assert_eq!(offset_of!(Padded, scale), 144);
+        assert_eq!(Padded::SIZE, 160);
+        assert_eq!(hlsl_buffer_size::<Padded>(), 160);
+    }
+
+    #[test]
+    fn arrays_and_nested_structs() {
+        assert_eq!(<[Element; 3]>::SIZE, 48);
+        assert_eq!(offset_of!(Nested, count), 48);
+        assert_eq!(offset_of!(Nested, tail), 52);
+        assert_eq!(Nested::SIZE, 60);
+        assert_eq!(hlsl_buffer_size::<Nested>(), 64);
+        assert_eq!(<[Element; 0]>::SIZE, 0);
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(hlsl_offset(12, 4, HLSLPacking::Packed), 12);
+        assert_eq!(hlsl_offset(12, 8, HLSLPacking::Packed), 16);
+        assert_eq!(hlsl_offset(16, 16, HLSLPacking::Packed), 16);
+        assert_eq!(hlsl_offset(4, 64, HLSLPacking::Register), 16);
+        assert_eq!(hlsl_offset(44, 4, HLSLPacking::Packed), 44);
+        assert_eq!(hlsl_offset(44, 4, HLSLPacking::Padding), 44);
+        assert_eq!(PaddedMatrix3::SIZE, 44);
+    }
+
+    #[test]
+    fn buffer_size() {
+        assert_eq!(hlsl_buffer_size::<f32>(), 16);
+        assert_eq!(hlsl_buffer_size::<LHRowMajorMatrix>(), 64);
+        assert_eq!(hlsl_buffer_size::<HLSLPadding<0>>(), 16);
+        assert_eq!(hlsl_bytes(&1.0f32), &1.0f32.to_ne_bytes());
+    }
+
+    #[test]
+    fn structured_members_are_tightly_packed() {
+        assert_eq!(offset_of!(Particle, uv), 12);
+        assert_eq!(structured_stride::<Particle>(), 20);
+        assert_eq!(offset_of!(Instance, normal), 64);
+        assert_eq!(offset_of!(Instance, particles), 100);
+        assert_eq!(offset_of!(Instance, id), 140);
+        assert_eq!(structured_stride::<Instance>(), 144);
+        assert_eq!(structured_stride::<Vector3>(), 12);
+    }
+
+    #[test]
+    #[should_panic(expected = "at most 2048 bytes")]
+    fn structured_stride_is_limited() {
+        structured_stride::<[Vector4; 129]>();
+    }
+}
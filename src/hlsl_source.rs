@@ -0,0 +1,187 @@
+// Just enough of an HLSL tokenizer to find function definitions. Comments, string literals and
+// preprocessor lines are dropped, and numbers are kept only so they separate other tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Identifier(&'a str),
+    Symbol(char),
+    Number,
+}
+
+fn tokenize(code: &str) -> Vec<Token<'_>> {
+    let bytes = code.as_bytes();
+    let mut tokens = Vec::new();
+
+    let mut i = 0;
+    let mut line_start = true;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let next = bytes.get(i + 1).copied();
+
+        if c.is_ascii_whitespace() {
+            line_start |= c == b'\n';
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        match c {
+            b'/' if next == Some(b'/') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if next == Some(b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            b'"' | b'\'' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != c {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            // Directives run to the end of the line, including escaped line breaks
+            b'#' if line_start => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                    i += 1;
+                }
+                tokens.push(Token::Identifier(&code[start..i]));
+            }
+            c if c.is_ascii_digit() => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                tokens.push(Token::Number);
+            }
+            c => {
+                if c.is_ascii() {
+                    tokens.push(Token::Symbol(c as char));
+                }
+                i += 1;
+            }
+        }
+
+        line_start = false;
+    }
+
+    tokens
+}
+
+// Matches a parameter list, an optional semantic and then the body of a function
+fn starts_definition(tokens: &[Token]) -> bool {
+    let mut tokens = tokens.iter();
+    if tokens.next() != Some(&Token::Symbol('(')) {
+        return false;
+    }
+
+    let mut depth = 1;
+    while depth > 0 {
+        match tokens.next() {
+            Some(Token::Symbol('(')) => depth += 1,
+            Some(Token::Symbol(')')) => depth -= 1,
+            Some(_) => {}
+            None => return false,
+        }
+    }
+
+    match tokens.next() {
+        Some(Token::Symbol('{')) => true,
+        Some(Token::Symbol(':')) => {
+            matches!(tokens.next(), Some(Token::Identifier(_)))
+                && tokens.next() == Some(&Token::Symbol('{'))
+        }
+        _ => false,
+    }
+}
+
+// True if the code defines a function with the given name at global scope. Calls, prototypes,
+// methods and mentions in comments or strings do not count.
+pub(crate) fn defines_function(code: &str, name: &str) -> bool {
+    let tokens = tokenize(code);
+
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Symbol('{') => depth += 1,
+            Token::Symbol('}') => depth = depth.saturating_sub(1),
+            // The return type comes first
+            Token::Identifier(identifier)
+                if depth == 0
+                    && *identifier == name
+                    && i > 0
+                    && matches!(tokens[i - 1], Token::Identifier(_))
+                    && starts_definition(&tokens[i + 1..]) =>
+            {
+                return true
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_definitions() {
+        assert!(defines_function(
+            "float4 geometry_main(float4 position : POSITION) : SV_POSITION { return position; }",
+            "geometry_main"
+        ));
+        assert!(defines_function(
+            "[maxvertexcount(3)]\nvoid geometry_main(triangle float4 input[3], inout TriangleStream<float4> output)\n{\n}",
+            "geometry_main"
+        ));
+        assert!(defines_function(
+            "struct Output { float4 position : SV_POSITION; };\n\nOutput hull_main ( uint id : SV_OutputControlPointID ) { Output output; return output; }",
+            "hull_main"
+        ));
+    }
+
+    #[test]
+    fn ignores_comments_and_strings() {
+        let code = "// void geometry_main() {}\n\
+            /* float4 geometry_main(float4 p) : SV_POSITION { return p; } */\n\
+            static const string name = \"void geometry_main() {\";\n\
+            float4 vertex_main() : SV_POSITION { return 0; }";
+        assert!(!defines_function(code, "geometry_main"));
+        assert!(defines_function(code, "vertex_main"));
+    }
+
+    #[test]
+    fn ignores_calls_prototypes_and_methods() {
+        let code = "float4 geometry_main(float4 p);\n\
+            float4 helper(float4 p) { return geometry_main(p) + other.geometry_main(p); }\n\
+            struct Foo { float4 geometry_main(float4 p) { return p; } };\n\
+            static float4 value = geometry_main(0);";
+        assert!(!defines_function(code, "geometry_main"));
+        assert!(defines_function(code, "helper"));
+    }
+
+    #[test]
+    fn ignores_preprocessor_lines() {
+        let code = "#define ENTRY void domain_main() {}\n\
+            #define LONG \\\n    void domain_main() {}\n\
+            float4 vertex_main() : SV_POSITION { return 0; }";
+        assert!(!defines_function(code, "domain_main"));
+        assert!(defines_function(code, "vertex_main"));
+    }
+
+    #[test]
+    fn requires_the_whole_name() {
+        let code = "void my_domain_main() {}\nvoid domain_main2() {}";
+        assert!(!defines_function(code, "domain_main"));
+    }
+}
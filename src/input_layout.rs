@@ -0,0 +1,334 @@
+use crate::{ShaderInputParameter, ShaderScalarType, ShaderVariableType};
+use alexandria_common::Format;
+
+// An element of an input layout, with the semantic index split off its name
+#[derive(Debug, Clone)]
+pub(crate) struct VertexElement {
+    semantic_name: String,
+    semantic_index: usize,
+    variable_type: ShaderVariableType,
+    format: win32::DXGIFormat,
+}
+
+// Where the input layout of a shader comes from
+#[derive(Debug, Clone, Copy)]
+pub enum VertexLayout<'a> {
+    // Elements named by semantic, such as "TEXCOORD1", in the order they appear in a vertex.
+    // Checked against the inputs of the vertex shader.
+    Explicit(&'a [(&'a str, Format)]),
+    // One element per input of the vertex shader, in the order the shader declares them and
+    // exactly as wide as it declares them. Vertices must be laid out the same way, which
+    // Shader::vertex_stride helps check.
+    Reflected,
+}
+
+#[derive(Debug)]
+pub enum InputLayoutError {
+    // The vertex shader reads a semantic the layout does not provide
+    MissingSemantic(String),
+    // The layout provides a semantic with another scalar type, or without a component the
+    // vertex shader reads
+    TypeMismatch {
+        semantic: String,
+        expected: ShaderVariableType,
+        found: ShaderVariableType,
+    },
+    // The vertex shader reads an input no layout can be generated for
+    UnsupportedInput {
+        semantic: String,
+        variable_type: ShaderVariableType,
+    },
+}
+
+fn semantic(name: &str, index: usize) -> String {
+    match index {
+        0 => name.to_string(),
+        _ => format!("{}{}", name, index),
+    }
+}
+
+// Splits "TEXCOORD1" into "TEXCOORD" and 1, like HLSL does
+fn split_semantic(semantic: &str) -> (&str, usize) {
+    let name = semantic.trim_end_matches(|c: char| c.is_ascii_digit());
+    (name, semantic[name.len()..].parse().unwrap_or(0))
+}
+
+fn input_format(scalar: ShaderScalarType, components: usize) -> Option<win32::DXGIFormat> {
+    Some(match (scalar, components) {
+        (ShaderScalarType::Float, 1) => win32::DXGIFormat::R32Float,
+        (ShaderScalarType::Float, 2) => win32::DXGIFormat::R32A32Float,
+        (ShaderScalarType::Float, 3) => win32::DXGIFormat::R32G32A32Float,
+        (ShaderScalarType::Float, 4) => win32::DXGIFormat::R32G32B32A32Float,
+        (ShaderScalarType::Int, 1) => win32::DXGIFormat::R32Sint,
+        (ShaderScalarType::UInt, 1) => win32::DXGIFormat::R32Uint,
+        _ => return None,
+    })
+}
+
+// None for a reflected layout, which is only known once the vertex shader is compiled
+pub(crate) fn convert_vertex_layout(vertex_layout: VertexLayout) -> Option<Vec<VertexElement>> {
+    let vertex_layout = match vertex_layout {
+        VertexLayout::Explicit(vertex_layout) => vertex_layout,
+        VertexLayout::Reflected => return None,
+    };
+
+    let elements = vertex_layout
+        .iter()
+        .map(|(name, format)| {
+            let (semantic_name, semantic_index) = split_semantic(name);
+            let components = match format {
+                Format::R32G32B32A32Float => 4,
+                Format::R32G32B32Float => 3,
+                Format::R32A32Float => 2,
+            };
+
+            VertexElement {
+                semantic_name: semantic_name.to_string(),
+                semantic_index,
+                variable_type: ShaderVariableType::new(ShaderScalarType::Float, 1, components),
+                format: crate::alexandria_to_dxgi(format),
+            }
+        })
+        .collect();
+
+    Some(elements)
+}
+
+// One element per input of the vertex shader, in the order the shader declares them. The
+// component mask of each input decides its width.
+fn reflect_input_layout(
+    signature: &[ShaderInputParameter],
+) -> Result<Vec<VertexElement>, InputLayoutError> {
+    signature
+        .iter()
+        .map(|parameter| {
+            let variable_type =
+                ShaderVariableType::new(parameter.scalar(), 1, parameter.components());
+
+            match input_format(parameter.scalar(), parameter.components()) {
+                Some(format) => Ok(VertexElement {
+                    semantic_name: parameter.semantic_name().to_string(),
+                    semantic_index: parameter.semantic_index(),
+                    variable_type,
+                    format,
+                }),
+                None => Err(InputLayoutError::UnsupportedInput {
+                    semantic: semantic(parameter.semantic_name(), parameter.semantic_index()),
+                    variable_type,
+                }),
+            }
+        })
+        .collect()
+}
+
+// Reflects the layout when vertex_layout is None. Otherwise checks that vertex_layout provides
+// every input with the right scalar type and every component the shader reads. D3D11 fills in
+// missing components with 0, except w with 1, so a float3 may still feed a float4 position
+// but a float2 feeding a float3 is almost certainly a mistake. Extra components are ignored.
+pub(crate) fn resolve_input_layout(
+    vertex_layout: Option<&[VertexElement]>,
+    signature: &[ShaderInputParameter],
+) -> Result<Vec<VertexElement>, InputLayoutError> {
+    let vertex_layout = match vertex_layout {
+        Some(vertex_layout) => vertex_layout,
+        None => return reflect_input_layout(signature),
+    };
+
+    for parameter in signature {
+        let semantic = semantic(parameter.semantic_name(), parameter.semantic_index());
+
+        // Semantics are case insensitive in HLSL
+        let element = vertex_layout
+            .iter()
+            .find(|element| {
+                element
+                    .semantic_name
+                    .eq_ignore_ascii_case(parameter.semantic_name())
+                    && element.semantic_index == parameter.semantic_index()
+            })
+            .ok_or_else(|| InputLayoutError::MissingSemantic(semantic.clone()))?;
+
+        if element.variable_type.scalar() != parameter.scalar()
+            || element.variable_type.columns() < parameter.read_components().min(3)
+        {
+            return Err(InputLayoutError::TypeMismatch {
+                semantic,
+                expected: ShaderVariableType::new(parameter.scalar(), 1, parameter.components()),
+                found: element.variable_type,
+            });
+        }
+    }
+
+    Ok(vertex_layout.to_vec())
+}
+
+impl VertexElement {
+    pub(crate) fn semantic_name(&self) -> &str {
+        &self.semantic_name
+    }
+
+    pub(crate) fn semantic_index(&self) -> usize {
+        self.semantic_index
+    }
+
+    pub(crate) fn format(&self) -> win32::DXGIFormat {
+        self.format
+    }
+
+    // Every format is made of 32 bit components
+    pub(crate) fn size(&self) -> usize {
+        self.variable_type.columns() * 4
+    }
+}
+
+impl std::error::Error for InputLayoutError {}
+
+impl std::fmt::Display for InputLayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputLayoutError::MissingSemantic(semantic) => write!(
+                f,
+                "The vertex layout is missing the semantic \"{}\" read by the vertex shader",
+                semantic
+            ),
+            InputLayoutError::TypeMismatch {
+                semantic,
+                expected,
+                found,
+            } => write!(
+                f,
+                "The vertex layout provides the semantic \"{}\" as a {}, but the vertex shader reads a {}",
+                semantic, found, expected
+            ),
+            InputLayoutError::UnsupportedInput {
+                semantic,
+                variable_type,
+            } => write!(
+                f,
+                "Cannot generate a vertex layout for the semantic \"{}\" of type {}",
+                semantic, variable_type
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature() -> Vec<ShaderInputParameter> {
+        vec![
+            ShaderInputParameter::new("POSITION", 0, ShaderScalarType::Float, 3, 3),
+            ShaderInputParameter::new("TEXCOORD", 1, ShaderScalarType::Float, 2, 2),
+        ]
+    }
+
+    fn explicit(vertex_layout: &[(&str, Format)]) -> Vec<VertexElement> {
+        convert_vertex_layout(VertexLayout::Explicit(vertex_layout)).unwrap()
+    }
+
+    #[test]
+    fn reflected_layout_follows_the_signature() {
+        assert!(convert_vertex_layout(VertexLayout::Reflected).is_none());
+
+        let vertex_layout = resolve_input_layout(None, &signature()).unwrap();
+        let elements: Vec<_> = vertex_layout
+            .iter()
+            .map(|element| {
+                (
+                    element.semantic_name(),
+                    element.semantic_index(),
+                    element.size(),
+                )
+            })
+            .collect();
+        assert_eq!(elements, [("POSITION", 0, 12), ("TEXCOORD", 1, 8)]);
+
+        let signature = [ShaderInputParameter::new(
+            "BLEND",
+            0,
+            ShaderScalarType::Int,
+            2,
+            2,
+        )];
+        assert!(matches!(
+            resolve_input_layout(None, &signature),
+            Err(InputLayoutError::UnsupportedInput { semantic, .. }) if semantic == "BLEND"
+        ));
+    }
+
+    #[test]
+    fn explicit_layout_matches_by_semantic() {
+        let vertex_layout = explicit(&[
+            ("texcoord1", Format::R32A32Float),
+            ("POSITION", Format::R32G32B32A32Float),
+        ]);
+        let resolved = resolve_input_layout(Some(&vertex_layout), &signature()).unwrap();
+        assert_eq!(resolved[0].semantic_name(), "texcoord");
+        assert_eq!(resolved[0].semantic_index(), 1);
+        assert_eq!(resolved.iter().map(VertexElement::size).sum::<usize>(), 24);
+    }
+
+    #[test]
+    fn explicit_layout_must_provide_every_input() {
+        let vertex_layout = explicit(&[("POSITION", Format::R32G32B32Float)]);
+        assert!(matches!(
+            resolve_input_layout(Some(&vertex_layout), &signature()),
+            Err(InputLayoutError::MissingSemantic(semantic)) if semantic == "TEXCOORD1"
+        ));
+
+        // An empty layout is not a request to reflect one
+        assert!(matches!(
+            resolve_input_layout(Some(&[]), &signature()),
+            Err(InputLayoutError::MissingSemantic(semantic)) if semantic == "POSITION"
+        ));
+        assert!(resolve_input_layout(Some(&[]), &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn explicit_layout_must_match_type_and_components() {
+        let vertex_layout = explicit(&[
+            ("POSITION", Format::R32A32Float),
+            ("TEXCOORD1", Format::R32A32Float),
+        ]);
+        assert!(matches!(
+            resolve_input_layout(Some(&vertex_layout), &signature()),
+            Err(InputLayoutError::TypeMismatch { semantic, .. }) if semantic == "POSITION"
+        ));
+
+        // A float3 feeds a float4 position, with w filled in as 1
+        let vertex_layout = explicit(&[("POSITION", Format::R32G32B32Float)]);
+        let signature = [ShaderInputParameter::new(
+            "POSITION",
+            0,
+            ShaderScalarType::Float,
+            4,
+            4,
+        )];
+        assert!(resolve_input_layout(Some(&vertex_layout), &signature).is_ok());
+
+        // Components the shader declares but never reads need not be provided
+        let vertex_layout = explicit(&[("COLOR", Format::R32A32Float)]);
+        let signature = [ShaderInputParameter::new(
+            "COLOR",
+            0,
+            ShaderScalarType::Float,
+            4,
+            2,
+        )];
+        assert!(resolve_input_layout(Some(&vertex_layout), &signature).is_ok());
+
+        let vertex_layout = explicit(&[("BLENDINDICES", Format::R32G32B32A32Float)]);
+        let signature = [ShaderInputParameter::new(
+            "BLENDINDICES",
+            0,
+            ShaderScalarType::UInt,
+            1,
+            1,
+        )];
+        assert!(matches!(
+            resolve_input_layout(Some(&vertex_layout), &signature),
+            Err(InputLayoutError::TypeMismatch { semantic, .. }) if semantic == "BLENDINDICES"
+        ));
+    }
+}
@@ -1,17 +1,62 @@
+mod animation;
+mod bounds;
+mod compute_shader;
 mod constant_buffer;
+mod constant_ring;
+mod constant_ring_allocator;
+mod constant_shadow;
+mod frustum;
 mod graphics;
+mod hlsl_layout;
+mod hlsl_source;
+mod input_layout;
 mod matrix;
+mod matrix3;
+mod matrix64;
+mod matrix_math;
+mod matrix_variants;
 mod mesh;
+mod quaternion;
+mod ray;
+mod scene_graph;
 mod shader;
+mod shader_cache;
+mod shader_constant_data;
+mod shader_constants;
+mod shader_reflection;
+mod shader_stages;
+mod structured_buffer;
 mod texture2d;
+mod transform;
 mod viewport;
 mod window;
 
+pub use animation::*;
+pub use bounds::*;
+pub use compute_shader::*;
 pub use constant_buffer::*;
+pub use constant_ring::*;
+pub use constant_ring_allocator::*;
+pub use frustum::*;
+pub use graphics::DepthMode;
+pub use hlsl_layout::*;
+pub use input_layout::*;
 pub use matrix::*;
+pub use matrix3::*;
+pub use matrix64::*;
+pub use matrix_variants::*;
 pub use mesh::*;
+pub use quaternion::*;
+pub use ray::*;
+pub use scene_graph::*;
 pub use shader::*;
+pub use shader_cache::*;
+pub use shader_constants::*;
+pub use shader_reflection::*;
+pub use shader_stages::*;
+pub use structured_buffer::*;
 pub use texture2d::*;
+pub use transform::*;
 pub use viewport::*;
 pub use window::*;
 
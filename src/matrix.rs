@@ -1,7 +1,9 @@
+use crate::{matrix_math, Quaternion};
 use alexandria_common::{Matrix, Vector3, Vector4};
 use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Sub, SubAssign};
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(C)]
 pub struct LHRowMajorMatrix([f32; 4 * 4]);
 
@@ -11,161 +13,401 @@ impl Matrix for LHRowMajorMatrix {
     }
 
     fn identity() -> Self {
-        let mut matrix = LHRowMajorMatrix::zero();
-        matrix.set(0, 0, 1.0);
-        matrix.set(1, 1, 1.0);
-        matrix.set(2, 2, 1.0);
-        matrix.set(3, 3, 1.0);
-        matrix
+        LHRowMajorMatrix(matrix_math::identity())
     }
 
     fn look_at(position: Vector3, target: Vector3, up: Vector3) -> LHRowMajorMatrix {
-        let z_axis = (target - position).normal();
-        let x_axis = (up.cross(z_axis)).normal();
-        let y_axis = z_axis.cross(x_axis);
-
-        let mut matrix = LHRowMajorMatrix::zero();
-        matrix.set(0, 0, x_axis.x());
-        matrix.set(0, 1, x_axis.y());
-        matrix.set(0, 2, x_axis.z());
-        matrix.set(0, 3, -x_axis.dot(position));
-        matrix.set(1, 0, y_axis.x());
-        matrix.set(1, 1, y_axis.y());
-        matrix.set(1, 2, y_axis.z());
-        matrix.set(1, 3, -y_axis.dot(position));
-        matrix.set(2, 0, z_axis.x());
-        matrix.set(2, 1, z_axis.y());
-        matrix.set(2, 2, z_axis.z());
-        matrix.set(2, 3, -z_axis.dot(position));
-        matrix.set(3, 3, 1.0);
-        matrix
+        let array = |vector: Vector3| [vector.x(), vector.y(), vector.z()];
+        LHRowMajorMatrix(matrix_math::look_at(
+            array(position),
+            array(target),
+            array(up),
+        ))
     }
 
     fn scale(x: f32, y: f32, z: f32) -> LHRowMajorMatrix {
-        let mut matrix = LHRowMajorMatrix::identity();
-        matrix.set(0, 0, x);
-        matrix.set(1, 1, y);
-        matrix.set(2, 2, z);
-        matrix
+        LHRowMajorMatrix(matrix_math::scale(x, y, z))
     }
 
     fn translation(x: f32, y: f32, z: f32) -> LHRowMajorMatrix {
-        let mut matrix = LHRowMajorMatrix::identity();
-        matrix.set(0, 3, x);
-        matrix.set(1, 3, y);
-        matrix.set(2, 3, z);
-        matrix
+        LHRowMajorMatrix(matrix_math::translation(x, y, z))
     }
 
     fn rotation(x: f32, y: f32, z: f32) -> LHRowMajorMatrix {
-        let mut matrix = LHRowMajorMatrix::identity();
+        LHRowMajorMatrix(matrix_math::rotation(x, y, z))
+    }
 
-        let cos_a = z.cos();
-        let sin_a = z.sin();
-        let cos_b = y.cos();
-        let sin_b = y.sin();
-        let cos_g = x.cos();
-        let sin_g = x.sin();
+    fn rotation_x(angle: f32) -> LHRowMajorMatrix {
+        LHRowMajorMatrix(matrix_math::rotation_x(angle))
+    }
 
-        matrix.set(0, 0, cos_a * cos_b);
-        matrix.set(0, 1, sin_a * cos_b);
-        matrix.set(0, 2, -sin_b);
+    fn rotation_y(angle: f32) -> LHRowMajorMatrix {
+        LHRowMajorMatrix(matrix_math::rotation_y(angle))
+    }
 
-        matrix.set(1, 0, cos_a * sin_b * sin_g - sin_a * cos_g);
-        matrix.set(1, 1, sin_a * sin_b * sin_g + cos_a * cos_g);
-        matrix.set(1, 2, cos_b * sin_g);
+    fn rotation_z(angle: f32) -> LHRowMajorMatrix {
+        LHRowMajorMatrix(matrix_math::rotation_z(angle))
+    }
 
-        matrix.set(2, 0, cos_a * sin_b * cos_g + sin_a * sin_g);
-        matrix.set(2, 1, sin_a * sin_b * cos_g - cos_a * sin_g);
-        matrix.set(2, 2, cos_b * cos_g);
+    fn orthographic(width: f32, height: f32, near: f32, far: f32) -> LHRowMajorMatrix {
+        LHRowMajorMatrix(matrix_math::orthographic(width, height, near, far))
+    }
 
-        matrix
+    fn perspective(fovy: f32, aspect: f32, near: f32, far: f32) -> LHRowMajorMatrix {
+        LHRowMajorMatrix(matrix_math::perspective(fovy, aspect, near, far))
     }
 
-    fn rotation_x(angle: f32) -> LHRowMajorMatrix {
-        let mut matrix = LHRowMajorMatrix::identity();
+    fn get(&self, col: usize, row: usize) -> f32 {
+        self.0[col * 4 + row]
+    }
 
-        let c = angle.cos();
-        let s = angle.sin();
+    fn set(&mut self, col: usize, row: usize, val: f32) {
+        self.0[col * 4 + row] = val
+    }
+}
 
-        matrix.set(1, 1, c);
-        matrix.set(2, 1, -s);
-        matrix.set(1, 2, s);
-        matrix.set(2, 2, c);
+impl LHRowMajorMatrix {
+    // A stable encoding of the elements in memory order as little endian f32s
+    pub fn to_le_bytes(&self) -> [u8; 4 * 4 * 4] {
+        let mut bytes = [0; 4 * 4 * 4];
+        for (chunk, val) in bytes.chunks_exact_mut(4).zip(self.0.iter()) {
+            chunk.copy_from_slice(&val.to_le_bytes());
+        }
+        bytes
+    }
 
-        matrix
+    pub fn from_le_bytes(bytes: [u8; 4 * 4 * 4]) -> LHRowMajorMatrix {
+        let mut vals = [0.0; 4 * 4];
+        for (val, chunk) in vals.iter_mut().zip(bytes.chunks_exact(4)) {
+            let mut array = [0; 4];
+            array.copy_from_slice(chunk);
+            *val = f32::from_le_bytes(array);
+        }
+        LHRowMajorMatrix(vals)
     }
 
-    fn rotation_y(angle: f32) -> LHRowMajorMatrix {
-        let mut matrix = LHRowMajorMatrix::identity();
+    // Transforms a column vector, computing M * v, which is the convention translation,
+    // look_at and the projections are built for
+    pub fn transform_vector(&self, vector: Vector4) -> Vector4 {
+        let [x, y, z, w] = transform(&self.0, [vector.x(), vector.y(), vector.z(), vector.w()]);
+        Vector4::new(x, y, z, w)
+    }
 
-        let c = angle.cos();
-        let s = angle.sin();
+    // Transforms every vector in place like transform_vector
+    pub fn transform_vectors(&self, vectors: &mut [Vector4]) {
+        transform_slice(&self.0, vectors)
+    }
 
-        matrix.set(0, 0, c);
-        matrix.set(2, 0, s);
-        matrix.set(0, 2, -s);
-        matrix.set(2, 2, c);
+    pub fn transpose(&self) -> LHRowMajorMatrix {
+        LHRowMajorMatrix(matrix_math::transpose(&self.0))
+    }
 
-        matrix
+    pub fn determinant(&self) -> f32 {
+        matrix_math::cofactors(&self.0).2
     }
 
-    fn rotation_z(angle: f32) -> LHRowMajorMatrix {
-        let mut matrix = LHRowMajorMatrix::identity();
+    pub fn inverse(&self) -> Option<LHRowMajorMatrix> {
+        matrix_math::inverse(&self.0).map(LHRowMajorMatrix)
+    }
 
-        let c = angle.cos();
-        let s = angle.sin();
+    // Only valid for matrices whose bottom row is (0, 0, 0, 1), such as those built from
+    // translation, rotation and scale
+    pub fn inverse_affine(&self) -> Option<LHRowMajorMatrix> {
+        let m = &self.0;
 
-        matrix.set(0, 0, c);
-        matrix.set(1, 0, -s);
-        matrix.set(0, 1, s);
-        matrix.set(1, 1, c);
+        // Inverse of the upper 3x3 via cofactors
+        let c00 = m[5] * m[10] - m[6] * m[9];
+        let c01 = m[6] * m[8] - m[4] * m[10];
+        let c02 = m[4] * m[9] - m[5] * m[8];
 
-        matrix
+        let determinant = m[0] * c00 + m[1] * c01 + m[2] * c02;
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        let inv_det = 1.0 / determinant;
+
+        let r00 = c00 * inv_det;
+        let r01 = (m[2] * m[9] - m[1] * m[10]) * inv_det;
+        let r02 = (m[1] * m[6] - m[2] * m[5]) * inv_det;
+        let r10 = c01 * inv_det;
+        let r11 = (m[0] * m[10] - m[2] * m[8]) * inv_det;
+        let r12 = (m[2] * m[4] - m[0] * m[6]) * inv_det;
+        let r20 = c02 * inv_det;
+        let r21 = (m[1] * m[8] - m[0] * m[9]) * inv_det;
+        let r22 = (m[0] * m[5] - m[1] * m[4]) * inv_det;
+
+        // Inverse translation
+        let (tx, ty, tz) = (m[3], m[7], m[11]);
+
+        Some(LHRowMajorMatrix([
+            r00,
+            r01,
+            r02,
+            -(r00 * tx + r01 * ty + r02 * tz),
+            r10,
+            r11,
+            r12,
+            -(r10 * tx + r11 * ty + r12 * tz),
+            r20,
+            r21,
+            r22,
+            -(r20 * tx + r21 * ty + r22 * tz),
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        ]))
     }
 
-    fn orthographic(width: f32, height: f32, near: f32, far: f32) -> LHRowMajorMatrix {
+    pub fn orthographic_off_center(
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near: f32,
+        far: f32,
+    ) -> LHRowMajorMatrix {
         let mut matrix = LHRowMajorMatrix::identity();
-        matrix.set(0, 0, 2.0 / width);
-        matrix.set(1, 1, 2.0 / height);
+        matrix.set(0, 0, 2.0 / (right - left));
+        matrix.set(1, 1, 2.0 / (top - bottom));
         matrix.set(2, 2, 1.0 / (far - near));
-        matrix.set(2, 3, -near / (far - near));
+        matrix.set(0, 3, (left + right) / (left - right));
+        matrix.set(1, 3, (top + bottom) / (bottom - top));
+        matrix.set(2, 3, near / (near - far));
         matrix
     }
 
-    fn perspective(fovy: f32, aspect: f32, near: f32, far: f32) -> LHRowMajorMatrix {
-        let y_scale = 1.0 / (fovy / 2.0).tan();
-        let x_scale = y_scale / aspect;
-
+    pub fn perspective_off_center(
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        near: f32,
+        far: f32,
+    ) -> LHRowMajorMatrix {
         let mut matrix = LHRowMajorMatrix::zero();
-        matrix.set(0, 0, x_scale);
-        matrix.set(1, 1, y_scale);
+        matrix.set(0, 0, 2.0 * near / (right - left));
+        matrix.set(1, 1, 2.0 * near / (top - bottom));
+        matrix.set(0, 2, (left + right) / (left - right));
+        matrix.set(1, 2, (top + bottom) / (bottom - top));
         matrix.set(2, 2, far / (far - near));
-        matrix.set(2, 3, -(near * far) / (far - near));
+        matrix.set(2, 3, (near * far) / (near - far));
         matrix.set(3, 2, 1.0);
         matrix
     }
 
-    fn get(&self, col: usize, row: usize) -> f32 {
-        self.0[col * 4 + row]
+    // Maps near to 1.0 and far to 0.0, for use with DepthMode::ReversedZ
+    pub fn perspective_reversed_z(fovy: f32, aspect: f32, near: f32, far: f32) -> LHRowMajorMatrix {
+        let mut matrix = LHRowMajorMatrix::perspective(fovy, aspect, near, far);
+        matrix.set(2, 2, near / (near - far));
+        matrix.set(2, 3, (near * far) / (far - near));
+        matrix
     }
 
-    fn set(&mut self, col: usize, row: usize, val: f32) {
-        self.0[col * 4 + row] = val
+    pub fn perspective_infinite(fovy: f32, aspect: f32, near: f32) -> LHRowMajorMatrix {
+        let mut matrix = LHRowMajorMatrix::perspective(fovy, aspect, near, near + 1.0);
+        matrix.set(2, 2, 1.0);
+        matrix.set(2, 3, -near);
+        matrix
+    }
+
+    pub fn perspective_infinite_reversed_z(fovy: f32, aspect: f32, near: f32) -> LHRowMajorMatrix {
+        let mut matrix = LHRowMajorMatrix::perspective(fovy, aspect, near, near + 1.0);
+        matrix.set(2, 2, 0.0);
+        matrix.set(2, 3, near);
+        matrix
+    }
+
+    // Composes translation * rotation * scale
+    pub fn from_trs(
+        translation: Vector3,
+        rotation: Quaternion,
+        scale: Vector3,
+    ) -> LHRowMajorMatrix {
+        let mut matrix = rotation.to_matrix();
+        for i in 0..3 {
+            matrix.set(i, 0, matrix.get(i, 0) * scale.x());
+            matrix.set(i, 1, matrix.get(i, 1) * scale.y());
+            matrix.set(i, 2, matrix.get(i, 2) * scale.z());
+        }
+
+        matrix.set(0, 3, translation.x());
+        matrix.set(1, 3, translation.y());
+        matrix.set(2, 3, translation.z());
+        matrix
+    }
+
+    // Splits a matrix composed as translation * rotation * scale back into its parts. A
+    // negative determinant is folded into the x scale. Returns None if any axis has no scale.
+    pub fn decompose(&self) -> Option<(Vector3, Quaternion, Vector3)> {
+        let translation = Vector3::new(self.get(0, 3), self.get(1, 3), self.get(2, 3));
+
+        let mut columns = [[0.0; 3]; 3];
+        let mut scale = [0.0; 3];
+        for j in 0..3 {
+            columns[j] = [self.get(0, j), self.get(1, j), self.get(2, j)];
+            scale[j] = (columns[j][0] * columns[j][0]
+                + columns[j][1] * columns[j][1]
+                + columns[j][2] * columns[j][2])
+                .sqrt();
+
+            if scale[j] <= f32::EPSILON || !scale[j].is_finite() {
+                return None;
+            }
+        }
+
+        // Handle reflections
+        let determinant = columns[0][0]
+            * (columns[1][1] * columns[2][2] - columns[1][2] * columns[2][1])
+            - columns[1][0] * (columns[0][1] * columns[2][2] - columns[0][2] * columns[2][1])
+            + columns[2][0] * (columns[0][1] * columns[1][2] - columns[0][2] * columns[1][1]);
+        if determinant < 0.0 {
+            scale[0] = -scale[0];
+        }
+
+        let mut rotation = LHRowMajorMatrix::identity();
+        for (j, (column, scale)) in columns.iter().zip(scale.iter()).enumerate() {
+            for (i, value) in column.iter().enumerate() {
+                rotation.set(i, j, value / scale);
+            }
+        }
+
+        Some((
+            translation,
+            Quaternion::from_matrix(&rotation),
+            Vector3::new(scale[0], scale[1], scale[2]),
+        ))
     }
 }
 
-impl Add for LHRowMajorMatrix {
-    type Output = LHRowMajorMatrix;
+// SSE2 is part of the x86_64 baseline, so no runtime detection is needed. Both paths sum
+// the products in the same order so they produce identical results, and the scalar path in
+// matrix_math is always compiled so the two can be compared.
+#[cfg(target_arch = "x86_64")]
+use self::{
+    multiply_sse as multiply, transform_row_sse as transform_row,
+    transform_slice_sse as transform_slice, transform_sse as transform,
+};
+
+#[cfg(not(target_arch = "x86_64"))]
+use self::{transform_row_scalar as transform_row, transform_slice_scalar as transform_slice};
+
+#[cfg(not(target_arch = "x86_64"))]
+use crate::matrix_math::{multiply, transform};
+
+// Treats the vector as a row, computing v * M
+#[cfg_attr(all(target_arch = "x86_64", not(test)), allow(dead_code))]
+fn transform_row_scalar(matrix: &[f32; 4 * 4], vector: [f32; 4]) -> [f32; 4] {
+    matrix_math::transform(&matrix_math::transpose(matrix), vector)
+}
+
+#[cfg_attr(all(target_arch = "x86_64", not(test)), allow(dead_code))]
+fn transform_slice_scalar(matrix: &[f32; 4 * 4], vectors: &mut [Vector4]) {
+    for vector in vectors {
+        let [x, y, z, w] =
+            matrix_math::transform(matrix, [vector.x(), vector.y(), vector.z(), vector.w()]);
+        *vector = Vector4::new(x, y, z, w);
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn multiply_sse(lhs: &[f32; 4 * 4], rhs: &[f32; 4 * 4]) -> [f32; 4 * 4] {
+    use std::arch::x86_64::*;
+
+    let mut ret = [0.0; 4 * 4];
+    unsafe {
+        let r0 = _mm_loadu_ps(rhs.as_ptr());
+        let r1 = _mm_loadu_ps(rhs.as_ptr().add(4));
+        let r2 = _mm_loadu_ps(rhs.as_ptr().add(8));
+        let r3 = _mm_loadu_ps(rhs.as_ptr().add(12));
 
-    fn add(mut self, rhs: LHRowMajorMatrix) -> LHRowMajorMatrix {
         for i in 0..4 {
-            for j in 0..4 {
-                self.set(i, j, self.get(i, j) + rhs.get(i, j))
-            }
+            let row = &lhs[i * 4..i * 4 + 4];
+            let mut sum = _mm_mul_ps(_mm_set1_ps(row[0]), r0);
+            sum = _mm_add_ps(sum, _mm_mul_ps(_mm_set1_ps(row[1]), r1));
+            sum = _mm_add_ps(sum, _mm_mul_ps(_mm_set1_ps(row[2]), r2));
+            sum = _mm_add_ps(sum, _mm_mul_ps(_mm_set1_ps(row[3]), r3));
+            _mm_storeu_ps(ret.as_mut_ptr().add(i * 4), sum);
         }
+    }
+    ret
+}
+
+// Gathers the columns straight from the matrix, so each vector is a sum of scaled columns
+// without transposing the matrix first
+#[cfg(target_arch = "x86_64")]
+fn load_columns(matrix: &[f32; 4 * 4]) -> [std::arch::x86_64::__m128; 4] {
+    use std::arch::x86_64::*;
+
+    let m = matrix;
+    unsafe {
+        [
+            _mm_setr_ps(m[0], m[4], m[8], m[12]),
+            _mm_setr_ps(m[1], m[5], m[9], m[13]),
+            _mm_setr_ps(m[2], m[6], m[10], m[14]),
+            _mm_setr_ps(m[3], m[7], m[11], m[15]),
+        ]
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn transform_columns(columns: &[std::arch::x86_64::__m128; 4], vector: [f32; 4]) -> [f32; 4] {
+    use std::arch::x86_64::*;
+
+    let mut ret = [0.0; 4];
+    unsafe {
+        let mut sum = _mm_mul_ps(_mm_set1_ps(vector[0]), columns[0]);
+        sum = _mm_add_ps(sum, _mm_mul_ps(_mm_set1_ps(vector[1]), columns[1]));
+        sum = _mm_add_ps(sum, _mm_mul_ps(_mm_set1_ps(vector[2]), columns[2]));
+        sum = _mm_add_ps(sum, _mm_mul_ps(_mm_set1_ps(vector[3]), columns[3]));
+        _mm_storeu_ps(ret.as_mut_ptr(), sum);
+    }
+    ret
+}
+
+#[cfg(target_arch = "x86_64")]
+fn transform_sse(matrix: &[f32; 4 * 4], vector: [f32; 4]) -> [f32; 4] {
+    transform_columns(&load_columns(matrix), vector)
+}
+
+// The rows are already contiguous, so v * M is a sum of scaled rows loaded as they are
+#[cfg(target_arch = "x86_64")]
+fn transform_row_sse(matrix: &[f32; 4 * 4], vector: [f32; 4]) -> [f32; 4] {
+    use std::arch::x86_64::*;
+
+    let rows = unsafe {
+        [
+            _mm_loadu_ps(matrix.as_ptr()),
+            _mm_loadu_ps(matrix.as_ptr().add(4)),
+            _mm_loadu_ps(matrix.as_ptr().add(8)),
+            _mm_loadu_ps(matrix.as_ptr().add(12)),
+        ]
+    };
+    transform_columns(&rows, vector)
+}
+
+#[cfg(target_arch = "x86_64")]
+fn transform_slice_sse(matrix: &[f32; 4 * 4], vectors: &mut [Vector4]) {
+    let columns = load_columns(matrix);
+    for vector in vectors {
+        let [x, y, z, w] =
+            transform_columns(&columns, [vector.x(), vector.y(), vector.z(), vector.w()]);
+        *vector = Vector4::new(x, y, z, w);
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for LHRowMajorMatrix {}
 
-        self
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for LHRowMajorMatrix {}
+
+impl Add for LHRowMajorMatrix {
+    type Output = LHRowMajorMatrix;
+
+    fn add(self, rhs: LHRowMajorMatrix) -> LHRowMajorMatrix {
+        LHRowMajorMatrix(matrix_math::add(self.0, &rhs.0))
     }
 }
 
@@ -178,14 +420,8 @@ impl AddAssign for LHRowMajorMatrix {
 impl Sub for LHRowMajorMatrix {
     type Output = LHRowMajorMatrix;
 
-    fn sub(mut self, rhs: LHRowMajorMatrix) -> LHRowMajorMatrix {
-        for i in 0..4 {
-            for j in 0..4 {
-                self.set(i, j, self.get(i, j) - rhs.get(i, j))
-            }
-        }
-
-        self
+    fn sub(self, rhs: LHRowMajorMatrix) -> LHRowMajorMatrix {
+        LHRowMajorMatrix(matrix_math::sub(self.0, &rhs.0))
     }
 }
 
@@ -195,28 +431,14 @@ impl SubAssign for LHRowMajorMatrix {
     }
 }
 
+// Treats the vector as a row, computing v * M, so a translation leaves points where they are.
+// Use transform_vector to transform points and directions.
 impl Mul<Vector4> for LHRowMajorMatrix {
     type Output = Vector4;
 
     fn mul(self, rhs: Vector4) -> Vector4 {
-        Vector4::new(
-            self.get(0, 0) * rhs.x()
-                + self.get(1, 0) * rhs.y()
-                + self.get(2, 0) * rhs.z()
-                + self.get(3, 0) * rhs.w(),
-            self.get(0, 1) * rhs.x()
-                + self.get(1, 1) * rhs.y()
-                + self.get(2, 1) * rhs.z()
-                + self.get(3, 1) * rhs.w(),
-            self.get(0, 2) * rhs.x()
-                + self.get(1, 2) * rhs.y()
-                + self.get(2, 2) * rhs.z()
-                + self.get(2, 3) * rhs.w(),
-            self.get(0, 3) * rhs.x()
-                + self.get(1, 3) * rhs.y()
-                + self.get(2, 3) * rhs.z()
-                + self.get(3, 3) * rhs.w(),
-        )
+        let [x, y, z, w] = transform_row(&self.0, [rhs.x(), rhs.y(), rhs.z(), rhs.w()]);
+        Vector4::new(x, y, z, w)
     }
 }
 
@@ -224,17 +446,7 @@ impl Mul for LHRowMajorMatrix {
     type Output = LHRowMajorMatrix;
 
     fn mul(self, rhs: LHRowMajorMatrix) -> LHRowMajorMatrix {
-        let mut ret = LHRowMajorMatrix::zero();
-
-        for i in 0..4 {
-            for j in 0..4 {
-                for k in 0..4 {
-                    ret.set(i, j, ret.get(i, j) + self.get(i, k) * rhs.get(k, j));
-                }
-            }
-        }
-
-        ret
+        LHRowMajorMatrix(multiply(&self.0, &rhs.0))
     }
 }
 
@@ -286,3 +498,400 @@ impl std::fmt::Display for LHRowMajorMatrix {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_matrix_eq(actual: &LHRowMajorMatrix, expected: &LHRowMajorMatrix) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!(
+                    (actual.get(i, j) - expected.get(i, j)).abs() < EPSILON,
+                    "element ({}, {}) differs\n{}\n{}",
+                    i,
+                    j,
+                    actual,
+                    expected
+                );
+            }
+        }
+    }
+
+    fn sequential() -> LHRowMajorMatrix {
+        LHRowMajorMatrix::from([
+            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0,
+        ])
+    }
+
+    fn general() -> LHRowMajorMatrix {
+        LHRowMajorMatrix::from([
+            2.0, 0.0, 1.0, 3.0, 1.0, 3.0, 0.0, 2.0, 0.0, 1.0, 4.0, 1.0, 1.0, 0.0, 2.0, 5.0,
+        ])
+    }
+
+    fn invertible() -> [LHRowMajorMatrix; 5] {
+        [
+            LHRowMajorMatrix::translation(1.0, -2.0, 3.0),
+            LHRowMajorMatrix::rotation(0.3, -1.2, 2.5),
+            LHRowMajorMatrix::scale(2.0, 0.5, -4.0),
+            LHRowMajorMatrix::perspective(1.2, 16.0 / 9.0, 0.1, 100.0),
+            general(),
+        ]
+    }
+
+    #[test]
+    fn inverse_times_matrix_is_identity() {
+        for matrix in invertible() {
+            let inverse = matrix.inverse().unwrap();
+            assert_matrix_eq(&(inverse * matrix), &LHRowMajorMatrix::identity());
+            assert_matrix_eq(&(matrix * inverse), &LHRowMajorMatrix::identity());
+        }
+    }
+
+    #[test]
+    fn inverse_affine_matches_inverse() {
+        let matrix = LHRowMajorMatrix::translation(4.0, 5.0, -6.0)
+            * LHRowMajorMatrix::rotation(0.4, 0.2, -0.9)
+            * LHRowMajorMatrix::scale(1.5, 2.0, 0.25);
+
+        let inverse = matrix.inverse_affine().unwrap();
+        assert_matrix_eq(&inverse, &matrix.inverse().unwrap());
+        assert_matrix_eq(&(inverse * matrix), &LHRowMajorMatrix::identity());
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        let mut repeated_row = general();
+        for j in 0..4 {
+            repeated_row.set(3, j, repeated_row.get(1, j));
+        }
+
+        assert!(LHRowMajorMatrix::zero().inverse().is_none());
+        assert!(repeated_row.inverse().is_none());
+        assert!(LHRowMajorMatrix::scale(1.0, 0.0, 1.0).inverse().is_none());
+        assert!(LHRowMajorMatrix::scale(1.0, 0.0, 1.0)
+            .inverse_affine()
+            .is_none());
+    }
+
+    #[test]
+    fn determinant_of_known_matrices() {
+        assert_eq!(LHRowMajorMatrix::identity().determinant(), 1.0);
+        assert_eq!(
+            LHRowMajorMatrix::translation(7.0, 8.0, 9.0).determinant(),
+            1.0
+        );
+        assert_eq!(LHRowMajorMatrix::scale(2.0, 3.0, 4.0).determinant(), 24.0);
+        assert!((LHRowMajorMatrix::rotation(0.5, 1.0, 1.5).determinant() - 1.0).abs() < EPSILON);
+        assert_eq!(general().determinant(), 80.0);
+
+        let matrix = LHRowMajorMatrix::from([
+            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 2.0, 6.0, 4.0, 8.0, 3.0, 1.0, 1.0, 2.0,
+        ]);
+        assert_eq!(matrix.determinant(), 72.0);
+        assert_eq!(matrix.transpose().determinant(), 72.0);
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let transpose = general().transpose();
+        for i in 0..4 {
+            for j in 0..4 {
+                assert_eq!(transpose.get(i, j), general().get(j, i));
+            }
+        }
+        assert_matrix_eq(&transpose.transpose(), &general());
+    }
+
+    fn assert_vector3_eq(actual: Vector3, expected: Vector3) {
+        assert!(
+            (actual.x() - expected.x()).abs() < EPSILON
+                && (actual.y() - expected.y()).abs() < EPSILON
+                && (actual.z() - expected.z()).abs() < EPSILON,
+            "({}, {}, {}) != ({}, {}, {})",
+            actual.x(),
+            actual.y(),
+            actual.z(),
+            expected.x(),
+            expected.y(),
+            expected.z()
+        );
+    }
+
+    #[test]
+    fn decompose_recompose_round_trip() {
+        let translation = Vector3::new(3.0, -4.0, 5.5);
+        let rotation = Quaternion::from_euler(0.4, -0.8, 1.9);
+        let scale = Vector3::new(2.0, 0.5, 3.0);
+
+        let matrix = LHRowMajorMatrix::from_trs(translation, rotation, scale);
+        let (t, r, s) = matrix.decompose().unwrap();
+        assert_vector3_eq(t, translation);
+        assert!((r.dot(rotation).abs() - 1.0).abs() < EPSILON);
+        assert_vector3_eq(s, scale);
+        assert_matrix_eq(&LHRowMajorMatrix::from_trs(t, r, s), &matrix);
+
+        // Composed from the individual constructors
+        let matrix = LHRowMajorMatrix::translation(1.0, 2.0, 3.0)
+            * LHRowMajorMatrix::rotation(0.4, -0.8, 1.9)
+            * LHRowMajorMatrix::scale(2.0, 0.5, 3.0);
+        let (t, r, s) = matrix.decompose().unwrap();
+        assert_vector3_eq(t, Vector3::new(1.0, 2.0, 3.0));
+        assert_matrix_eq(&r.to_matrix(), &LHRowMajorMatrix::rotation(0.4, -0.8, 1.9));
+        assert_vector3_eq(s, Vector3::new(2.0, 0.5, 3.0));
+    }
+
+    #[test]
+    fn decompose_negative_scale() {
+        let rotation = LHRowMajorMatrix::rotation(0.2, 0.3, -0.6);
+
+        // A negative x scale is returned as is
+        let matrix = rotation * LHRowMajorMatrix::scale(-2.0, 3.0, 4.0);
+        let (_, r, s) = matrix.decompose().unwrap();
+        assert_vector3_eq(s, Vector3::new(-2.0, 3.0, 4.0));
+        assert_matrix_eq(&r.to_matrix(), &rotation);
+
+        // Any other single reflection is folded into x with a compensating rotation
+        let matrix = rotation * LHRowMajorMatrix::scale(2.0, -3.0, 4.0);
+        let (t, r, s) = matrix.decompose().unwrap();
+        assert_vector3_eq(s, Vector3::new(-2.0, 3.0, 4.0));
+        assert_matrix_eq(&LHRowMajorMatrix::from_trs(t, r, s), &matrix);
+    }
+
+    #[test]
+    fn decompose_degenerate() {
+        assert!(LHRowMajorMatrix::zero().decompose().is_none());
+        assert!(LHRowMajorMatrix::scale(1.0, 0.0, 1.0).decompose().is_none());
+        assert!((LHRowMajorMatrix::rotation(0.1, 0.2, 0.3)
+            * LHRowMajorMatrix::scale(2.0, 2.0, 0.0))
+        .decompose()
+        .is_none());
+    }
+
+    fn bits(values: &[f32]) -> Vec<u32> {
+        values.iter().map(|value| value.to_bits()).collect()
+    }
+
+    fn vector_bits(vectors: &[Vector4]) -> Vec<u32> {
+        vectors
+            .iter()
+            .flat_map(|vector| [vector.x(), vector.y(), vector.z(), vector.w()])
+            .map(|value| value.to_bits())
+            .collect()
+    }
+
+    fn test_vectors() -> Vec<Vector4> {
+        (0..200)
+            .map(|i| {
+                let i = i as f32;
+                Vector4::new(i * 0.37 - 20.0, 1.0 / (i + 1.0), -i * 1.3, i % 2.0)
+            })
+            .collect()
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn sse_multiply_matches_scalar() {
+        for lhs in invertible() {
+            for rhs in invertible() {
+                assert_eq!(
+                    bits(&multiply_sse(&lhs.0, &rhs.0)),
+                    bits(&matrix_math::multiply(&lhs.0, &rhs.0))
+                );
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn sse_transform_matches_scalar() {
+        for matrix in invertible() {
+            for vector in test_vectors() {
+                let vector = [vector.x(), vector.y(), vector.z(), vector.w()];
+                assert_eq!(
+                    bits(&transform_sse(&matrix.0, vector)),
+                    bits(&matrix_math::transform(&matrix.0, vector))
+                );
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn sse_transform_row_matches_scalar() {
+        for matrix in invertible() {
+            for vector in test_vectors() {
+                let vector = [vector.x(), vector.y(), vector.z(), vector.w()];
+                assert_eq!(
+                    bits(&transform_row_sse(&matrix.0, vector)),
+                    bits(&transform_row_scalar(&matrix.0, vector))
+                );
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn sse_transform_slice_matches_scalar() {
+        for matrix in invertible() {
+            let mut sse = test_vectors();
+            let mut scalar = test_vectors();
+            transform_slice_sse(&matrix.0, &mut sse);
+            transform_slice_scalar(&matrix.0, &mut scalar);
+            assert_eq!(vector_bits(&sse), vector_bits(&scalar));
+        }
+    }
+
+    #[test]
+    fn transform_vectors_matches_transform_vector() {
+        let matrix = general();
+        let mut vectors = test_vectors();
+        matrix.transform_vectors(&mut vectors);
+
+        let expected: Vec<_> = test_vectors()
+            .into_iter()
+            .map(|vector| matrix.transform_vector(vector))
+            .collect();
+        assert_eq!(vector_bits(&vectors), vector_bits(&expected));
+    }
+
+    // D3DX documents its matrices for row vectors, so they are transposed before comparing
+    fn from_d3dx(rows: [[f32; 4]; 4]) -> LHRowMajorMatrix {
+        let mut matrix = LHRowMajorMatrix::zero();
+        for (i, row) in rows.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                matrix.set(j, i, *value);
+            }
+        }
+        matrix
+    }
+
+    #[test]
+    fn orthographic_off_center_matches_d3dx() {
+        let (l, r, b, t, zn, zf) = (-3.0, 5.0, 1.0, 7.0, 0.5, 50.0);
+
+        // D3DXMatrixOrthoOffCenterLH
+        let expected = from_d3dx([
+            [2.0 / (r - l), 0.0, 0.0, 0.0],
+            [0.0, 2.0 / (t - b), 0.0, 0.0],
+            [0.0, 0.0, 1.0 / (zf - zn), 0.0],
+            [(l + r) / (l - r), (t + b) / (b - t), zn / (zn - zf), 1.0],
+        ]);
+
+        assert_matrix_eq(
+            &LHRowMajorMatrix::orthographic_off_center(l, r, b, t, zn, zf),
+            &expected,
+        );
+    }
+
+    #[test]
+    fn perspective_off_center_matches_d3dx() {
+        let (l, r, b, t, zn, zf) = (-0.2, 0.1, -0.05, 0.15, 0.1, 100.0);
+
+        // D3DXMatrixPerspectiveOffCenterLH
+        let expected = from_d3dx([
+            [2.0 * zn / (r - l), 0.0, 0.0, 0.0],
+            [0.0, 2.0 * zn / (t - b), 0.0, 0.0],
+            [(l + r) / (l - r), (t + b) / (b - t), zf / (zf - zn), 1.0],
+            [0.0, 0.0, zn * zf / (zn - zf), 0.0],
+        ]);
+
+        assert_matrix_eq(
+            &LHRowMajorMatrix::perspective_off_center(l, r, b, t, zn, zf),
+            &expected,
+        );
+    }
+
+    #[test]
+    fn symmetric_off_center_matches_centered() {
+        let (width, height, near, far) = (16.0, 9.0, 0.1, 100.0);
+        assert_matrix_eq(
+            &LHRowMajorMatrix::orthographic_off_center(
+                -width / 2.0,
+                width / 2.0,
+                -height / 2.0,
+                height / 2.0,
+                near,
+                far,
+            ),
+            &LHRowMajorMatrix::orthographic(width, height, near, far),
+        );
+
+        let (fovy, aspect): (f32, f32) = (1.1, 16.0 / 9.0);
+        let half_height = near * (fovy / 2.0).tan();
+        let half_width = half_height * aspect;
+        assert_matrix_eq(
+            &LHRowMajorMatrix::perspective_off_center(
+                -half_width,
+                half_width,
+                -half_height,
+                half_height,
+                near,
+                far,
+            ),
+            &LHRowMajorMatrix::perspective(fovy, aspect, near, far),
+        );
+    }
+
+    #[test]
+    fn transform_vector_transforms_column_vectors() {
+        // Translation is stored in the last column, so it only moves points when the vector
+        // is on the right
+        let point = LHRowMajorMatrix::translation(1.0, 2.0, 3.0)
+            .transform_vector(Vector4::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(
+            [point.x(), point.y(), point.z(), point.w()],
+            [1.0, 2.0, 3.0, 1.0]
+        );
+
+        let vector = sequential().transform_vector(Vector4::new(1.0, 2.0, 3.0, 4.0));
+        assert_eq!(
+            [vector.x(), vector.y(), vector.z(), vector.w()],
+            [30.0, 70.0, 110.0, 150.0]
+        );
+    }
+
+    #[test]
+    fn mul_vector4_treats_vectors_as_rows() {
+        let point = LHRowMajorMatrix::translation(1.0, 2.0, 3.0) * Vector4::new(0.0, 0.0, 0.0, 1.0);
+        assert_eq!(
+            [point.x(), point.y(), point.z(), point.w()],
+            [0.0, 0.0, 0.0, 1.0]
+        );
+
+        // z once read the w term from element (2, 3) instead of (3, 2), giving 98
+        let vector = sequential() * Vector4::new(1.0, 2.0, 3.0, 4.0);
+        assert_eq!(
+            [vector.x(), vector.y(), vector.z(), vector.w()],
+            [90.0, 100.0, 110.0, 120.0]
+        );
+
+        for vector in test_vectors() {
+            let expected = general().transpose().transform_vector(vector);
+            assert_eq!(vector_bits(&[general() * vector]), vector_bits(&[expected]));
+        }
+    }
+
+    #[test]
+    fn le_bytes_are_in_memory_order() {
+        let matrix = sequential();
+        let bytes = matrix.to_le_bytes();
+
+        assert_eq!(bytes[0..4], 1.0f32.to_le_bytes());
+        assert_eq!(bytes[4..8], 2.0f32.to_le_bytes());
+        assert_eq!(bytes[60..64], 16.0f32.to_le_bytes());
+        assert_matrix_eq(&LHRowMajorMatrix::from_le_bytes(bytes), &matrix);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips() {
+        let matrix = general();
+        let json = serde_json::to_string(&matrix).unwrap();
+
+        assert_matrix_eq(&serde_json::from_str(&json).unwrap(), &matrix);
+    }
+}
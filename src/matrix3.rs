@@ -0,0 +1,364 @@
+use crate::LHRowMajorMatrix;
+use alexandria_common::{Matrix, Vector3};
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Sub, SubAssign};
+
+// Uses the same element layout and indexing as LHRowMajorMatrix
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct Matrix3([f32; 3 * 3]);
+
+// Matches the register layout of a float3x3 in an HLSL constant buffer, where every row of the
+// matrix starts a new 16 byte register. HLSL packs a following scalar into the padding of the
+// last register, so follow this with a 16 byte aligned member or explicit padding.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct PaddedMatrix3([[f32; 4]; 3]);
+
+impl Matrix3 {
+    pub fn zero() -> Self {
+        Matrix3([0.0; 3 * 3])
+    }
+
+    pub fn identity() -> Self {
+        let mut matrix = Matrix3::zero();
+        matrix.set(0, 0, 1.0);
+        matrix.set(1, 1, 1.0);
+        matrix.set(2, 2, 1.0);
+        matrix
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> f32 {
+        self.0[col * 3 + row]
+    }
+
+    pub fn set(&mut self, col: usize, row: usize, val: f32) {
+        self.0[col * 3 + row] = val
+    }
+
+    pub fn transpose(&self) -> Matrix3 {
+        let mut matrix = Matrix3::zero();
+        for i in 0..3 {
+            for j in 0..3 {
+                matrix.set(i, j, self.get(j, i));
+            }
+        }
+        matrix
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let m = &self.0;
+        m[0] * (m[4] * m[8] - m[5] * m[7]) - m[1] * (m[3] * m[8] - m[5] * m[6])
+            + m[2] * (m[3] * m[7] - m[4] * m[6])
+    }
+
+    pub fn inverse(&self) -> Option<Matrix3> {
+        let m = &self.0;
+
+        let determinant = self.determinant();
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        let inv_det = 1.0 / determinant;
+
+        Some(Matrix3([
+            (m[4] * m[8] - m[5] * m[7]) * inv_det,
+            (m[2] * m[7] - m[1] * m[8]) * inv_det,
+            (m[1] * m[5] - m[2] * m[4]) * inv_det,
+            (m[5] * m[6] - m[3] * m[8]) * inv_det,
+            (m[0] * m[8] - m[2] * m[6]) * inv_det,
+            (m[2] * m[3] - m[0] * m[5]) * inv_det,
+            (m[3] * m[7] - m[4] * m[6]) * inv_det,
+            (m[1] * m[6] - m[0] * m[7]) * inv_det,
+            (m[0] * m[4] - m[1] * m[3]) * inv_det,
+        ]))
+    }
+
+    pub fn to_matrix4(&self) -> LHRowMajorMatrix {
+        let mut matrix = LHRowMajorMatrix::identity();
+        for i in 0..3 {
+            for j in 0..3 {
+                matrix.set(i, j, self.get(i, j));
+            }
+        }
+        matrix
+    }
+}
+
+impl LHRowMajorMatrix {
+    pub fn upper_3x3(&self) -> Matrix3 {
+        let mut matrix = Matrix3::zero();
+        for i in 0..3 {
+            for j in 0..3 {
+                matrix.set(i, j, self.get(i, j));
+            }
+        }
+        matrix
+    }
+
+    // The inverse transpose of the upper 3x3, for transforming normals by a world matrix with
+    // non-uniform scale. Returns None if the matrix is singular.
+    pub fn normal_matrix(&self) -> Option<Matrix3> {
+        self.upper_3x3()
+            .inverse()
+            .map(|inverse| inverse.transpose())
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Matrix3 {}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Matrix3 {}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for PaddedMatrix3 {}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for PaddedMatrix3 {}
+
+impl Add for Matrix3 {
+    type Output = Matrix3;
+
+    fn add(mut self, rhs: Matrix3) -> Matrix3 {
+        for i in 0..3 * 3 {
+            self.0[i] += rhs.0[i];
+        }
+
+        self
+    }
+}
+
+impl AddAssign for Matrix3 {
+    fn add_assign(&mut self, rhs: Matrix3) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Matrix3 {
+    type Output = Matrix3;
+
+    fn sub(mut self, rhs: Matrix3) -> Matrix3 {
+        for i in 0..3 * 3 {
+            self.0[i] -= rhs.0[i];
+        }
+
+        self
+    }
+}
+
+impl SubAssign for Matrix3 {
+    fn sub_assign(&mut self, rhs: Matrix3) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<Vector3> for Matrix3 {
+    type Output = Vector3;
+
+    fn mul(self, rhs: Vector3) -> Vector3 {
+        Vector3::new(
+            self.get(0, 0) * rhs.x() + self.get(0, 1) * rhs.y() + self.get(0, 2) * rhs.z(),
+            self.get(1, 0) * rhs.x() + self.get(1, 1) * rhs.y() + self.get(1, 2) * rhs.z(),
+            self.get(2, 0) * rhs.x() + self.get(2, 1) * rhs.y() + self.get(2, 2) * rhs.z(),
+        )
+    }
+}
+
+impl Mul for Matrix3 {
+    type Output = Matrix3;
+
+    fn mul(self, rhs: Matrix3) -> Matrix3 {
+        let mut ret = Matrix3::zero();
+        for i in 0..3 {
+            for j in 0..3 {
+                ret.set(
+                    i,
+                    j,
+                    self.get(i, 0) * rhs.get(0, j)
+                        + self.get(i, 1) * rhs.get(1, j)
+                        + self.get(i, 2) * rhs.get(2, j),
+                );
+            }
+        }
+
+        ret
+    }
+}
+
+impl MulAssign for Matrix3 {
+    fn mul_assign(&mut self, rhs: Matrix3) {
+        *self = *self * rhs;
+    }
+}
+
+impl From<[f32; 3 * 3]> for Matrix3 {
+    fn from(vals: [f32; 3 * 3]) -> Matrix3 {
+        Matrix3(vals)
+    }
+}
+
+impl From<Matrix3> for [f32; 3 * 3] {
+    fn from(matrix: Matrix3) -> [f32; 3 * 3] {
+        matrix.0
+    }
+}
+
+impl From<Matrix3> for PaddedMatrix3 {
+    fn from(matrix: Matrix3) -> PaddedMatrix3 {
+        let m = &matrix.0;
+        PaddedMatrix3([
+            [m[0], m[1], m[2], 0.0],
+            [m[3], m[4], m[5], 0.0],
+            [m[6], m[7], m[8], 0.0],
+        ])
+    }
+}
+
+impl From<PaddedMatrix3> for Matrix3 {
+    fn from(matrix: PaddedMatrix3) -> Matrix3 {
+        let m = &matrix.0;
+        Matrix3([
+            m[0][0], m[0][1], m[0][2], m[1][0], m[1][1], m[1][2], m[2][0], m[2][1], m[2][2],
+        ])
+    }
+}
+
+impl Index<(usize, usize)> for Matrix3 {
+    type Output = f32;
+
+    fn index(&self, index: (usize, usize)) -> &Self::Output {
+        &self.0[index.0 + index.1 * 3]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix3 {
+    fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
+        &mut self.0[index.0 + index.1 * 3]
+    }
+}
+
+impl std::fmt::Display for Matrix3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for i in 0..3 {
+            writeln!(
+                f,
+                "| {: <5} {: <5} {: <5} |",
+                self.0[i * 3],
+                self.0[i * 3 + 1],
+                self.0[i * 3 + 2]
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_matrix_eq(actual: &Matrix3, expected: &Matrix3) {
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!(
+                    (actual.get(i, j) - expected.get(i, j)).abs() < EPSILON,
+                    "element ({}, {}) differs\n{}\n{}",
+                    i,
+                    j,
+                    actual,
+                    expected
+                );
+            }
+        }
+    }
+
+    fn general() -> Matrix3 {
+        Matrix3::from([2.0, 0.0, 1.0, 1.0, 3.0, 0.0, 0.0, 1.0, 4.0])
+    }
+
+    #[test]
+    fn inverse_times_matrix_is_identity() {
+        let matrix = general();
+        let inverse = matrix.inverse().unwrap();
+
+        assert_matrix_eq(&(inverse * matrix), &Matrix3::identity());
+        assert_matrix_eq(&(matrix * inverse), &Matrix3::identity());
+    }
+
+    #[test]
+    fn singular_matrix_has_no_inverse() {
+        // The last row is the sum of the first two
+        let matrix = Matrix3::from([1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 5.0, 7.0, 9.0]);
+
+        assert_eq!(matrix.determinant(), 0.0);
+        assert!(matrix.inverse().is_none());
+        assert!(Matrix3::zero().inverse().is_none());
+    }
+
+    #[test]
+    fn transpose_swaps_rows_and_columns() {
+        let matrix = Matrix3::from([1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+        let transpose = matrix.transpose();
+
+        assert_eq!(
+            <[f32; 9]>::from(transpose),
+            [1.0, 4.0, 7.0, 2.0, 5.0, 8.0, 3.0, 6.0, 9.0]
+        );
+        assert_matrix_eq(&transpose.transpose(), &matrix);
+    }
+
+    #[test]
+    fn normal_matrix_inverts_non_uniform_scale() {
+        let world = LHRowMajorMatrix::scale(2.0, 4.0, 8.0);
+        let expected = Matrix3::from([0.5, 0.0, 0.0, 0.0, 0.25, 0.0, 0.0, 0.0, 0.125]);
+
+        assert_matrix_eq(&world.normal_matrix().unwrap(), &expected);
+    }
+
+    #[test]
+    fn normal_matrix_keeps_normals_perpendicular() {
+        let world = LHRowMajorMatrix::rotation_z(0.7) * LHRowMajorMatrix::scale(1.0, 4.0, 1.0);
+        let tangent = Vector3::new(1.0, 1.0, 0.0);
+        let normal = Vector3::new(1.0, -1.0, 0.0);
+
+        let tangent = world.upper_3x3() * tangent;
+        let transformed = world.normal_matrix().unwrap() * normal;
+        assert!(tangent.dot(transformed).abs() < EPSILON);
+
+        // Transforming the normal like a position skews it under non-uniform scale
+        let skewed = world.upper_3x3() * normal;
+        assert!(tangent.dot(skewed).abs() > 1.0);
+    }
+
+    #[test]
+    fn singular_world_has_no_normal_matrix() {
+        assert!(LHRowMajorMatrix::scale(1.0, 0.0, 1.0)
+            .normal_matrix()
+            .is_none());
+    }
+
+    #[test]
+    fn padded_matrix_starts_every_row_in_a_new_register() {
+        let matrix = Matrix3::from([1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+        let padded = PaddedMatrix3::from(matrix);
+
+        assert_eq!(std::mem::size_of::<PaddedMatrix3>(), 3 * 16);
+        assert_eq!(
+            padded.0,
+            [
+                [1.0, 2.0, 3.0, 0.0],
+                [4.0, 5.0, 6.0, 0.0],
+                [7.0, 8.0, 9.0, 0.0]
+            ]
+        );
+        assert_eq!(
+            <[f32; 9]>::from(Matrix3::from(padded)),
+            <[f32; 9]>::from(matrix)
+        );
+    }
+}
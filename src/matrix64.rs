@@ -0,0 +1,336 @@
+use crate::{matrix_math, LHRowMajorMatrix};
+use alexandria_common::Matrix;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Sub, SubAssign};
+
+// A double precision counterpart to LHRowMajorMatrix for worlds too large for f32 positions.
+// Shaders only take f32, so convert with to_camera_relative before uploading.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct LHRowMajorMatrix64([f64; 4 * 4]);
+
+impl LHRowMajorMatrix64 {
+    pub fn zero() -> Self {
+        LHRowMajorMatrix64([0.0; 4 * 4])
+    }
+
+    pub fn identity() -> Self {
+        LHRowMajorMatrix64(matrix_math::identity())
+    }
+
+    pub fn look_at(position: [f64; 3], target: [f64; 3], up: [f64; 3]) -> Self {
+        LHRowMajorMatrix64(matrix_math::look_at(position, target, up))
+    }
+
+    pub fn scale(x: f64, y: f64, z: f64) -> Self {
+        LHRowMajorMatrix64(matrix_math::scale(x, y, z))
+    }
+
+    pub fn translation(x: f64, y: f64, z: f64) -> Self {
+        LHRowMajorMatrix64(matrix_math::translation(x, y, z))
+    }
+
+    pub fn rotation(x: f64, y: f64, z: f64) -> Self {
+        LHRowMajorMatrix64(matrix_math::rotation(x, y, z))
+    }
+
+    pub fn rotation_x(angle: f64) -> Self {
+        LHRowMajorMatrix64(matrix_math::rotation_x(angle))
+    }
+
+    pub fn rotation_y(angle: f64) -> Self {
+        LHRowMajorMatrix64(matrix_math::rotation_y(angle))
+    }
+
+    pub fn rotation_z(angle: f64) -> Self {
+        LHRowMajorMatrix64(matrix_math::rotation_z(angle))
+    }
+
+    pub fn orthographic(width: f64, height: f64, near: f64, far: f64) -> Self {
+        LHRowMajorMatrix64(matrix_math::orthographic(width, height, near, far))
+    }
+
+    pub fn perspective(fovy: f64, aspect: f64, near: f64, far: f64) -> Self {
+        LHRowMajorMatrix64(matrix_math::perspective(fovy, aspect, near, far))
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> f64 {
+        self.0[col * 4 + row]
+    }
+
+    pub fn set(&mut self, col: usize, row: usize, val: f64) {
+        self.0[col * 4 + row] = val
+    }
+
+    pub fn transpose(&self) -> Self {
+        LHRowMajorMatrix64(matrix_math::transpose(&self.0))
+    }
+
+    pub fn determinant(&self) -> f64 {
+        matrix_math::cofactors(&self.0).2
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        matrix_math::inverse(&self.0).map(LHRowMajorMatrix64)
+    }
+
+    // A stable encoding of the elements in memory order as little endian f64s
+    pub fn to_le_bytes(&self) -> [u8; 4 * 4 * 8] {
+        let mut bytes = [0; 4 * 4 * 8];
+        for (chunk, val) in bytes.chunks_exact_mut(8).zip(self.0.iter()) {
+            chunk.copy_from_slice(&val.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_le_bytes(bytes: [u8; 4 * 4 * 8]) -> Self {
+        let mut vals = [0.0; 4 * 4];
+        for (val, chunk) in vals.iter_mut().zip(bytes.chunks_exact(8)) {
+            let mut array = [0; 8];
+            array.copy_from_slice(chunk);
+            *val = f64::from_le_bytes(array);
+        }
+        LHRowMajorMatrix64(vals)
+    }
+
+    // Transforms a column vector, computing M * v like LHRowMajorMatrix::transform_vector
+    pub fn transform_vector(&self, vector: [f64; 4]) -> [f64; 4] {
+        matrix_math::transform(&self.0, vector)
+    }
+
+    pub fn transform_point(&self, point: [f64; 3]) -> [f64; 3] {
+        let [x, y, z, _] = matrix_math::transform(&self.0, [point[0], point[1], point[2], 1.0]);
+        [x, y, z]
+    }
+
+    // Moves the origin to the camera position in double precision before converting to f32,
+    // so positions near the camera keep their precision no matter how far they are from the
+    // world origin. Pair with a view matrix built with the camera at the origin.
+    pub fn to_camera_relative(&self, camera_position: [f64; 3]) -> LHRowMajorMatrix {
+        let relative = LHRowMajorMatrix64::translation(
+            -camera_position[0],
+            -camera_position[1],
+            -camera_position[2],
+        ) * *self;
+
+        relative.to_f32()
+    }
+
+    pub fn to_f32(&self) -> LHRowMajorMatrix {
+        let mut matrix = LHRowMajorMatrix::zero();
+        for i in 0..4 {
+            for j in 0..4 {
+                matrix.set(i, j, self.get(i, j) as f32);
+            }
+        }
+        matrix
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for LHRowMajorMatrix64 {}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for LHRowMajorMatrix64 {}
+
+impl From<LHRowMajorMatrix> for LHRowMajorMatrix64 {
+    fn from(matrix: LHRowMajorMatrix) -> LHRowMajorMatrix64 {
+        let mut ret = LHRowMajorMatrix64::zero();
+        for i in 0..4 {
+            for j in 0..4 {
+                ret.set(i, j, matrix.get(i, j) as f64);
+            }
+        }
+        ret
+    }
+}
+
+impl Add for LHRowMajorMatrix64 {
+    type Output = LHRowMajorMatrix64;
+
+    fn add(self, rhs: LHRowMajorMatrix64) -> LHRowMajorMatrix64 {
+        LHRowMajorMatrix64(matrix_math::add(self.0, &rhs.0))
+    }
+}
+
+impl AddAssign for LHRowMajorMatrix64 {
+    fn add_assign(&mut self, rhs: LHRowMajorMatrix64) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for LHRowMajorMatrix64 {
+    type Output = LHRowMajorMatrix64;
+
+    fn sub(self, rhs: LHRowMajorMatrix64) -> LHRowMajorMatrix64 {
+        LHRowMajorMatrix64(matrix_math::sub(self.0, &rhs.0))
+    }
+}
+
+impl SubAssign for LHRowMajorMatrix64 {
+    fn sub_assign(&mut self, rhs: LHRowMajorMatrix64) {
+        *self = *self - rhs;
+    }
+}
+
+// Treats the vector as a row, computing v * M like LHRowMajorMatrix does
+impl Mul<[f64; 4]> for LHRowMajorMatrix64 {
+    type Output = [f64; 4];
+
+    fn mul(self, rhs: [f64; 4]) -> [f64; 4] {
+        matrix_math::transform(&matrix_math::transpose(&self.0), rhs)
+    }
+}
+
+impl Mul for LHRowMajorMatrix64 {
+    type Output = LHRowMajorMatrix64;
+
+    fn mul(self, rhs: LHRowMajorMatrix64) -> LHRowMajorMatrix64 {
+        LHRowMajorMatrix64(matrix_math::multiply(&self.0, &rhs.0))
+    }
+}
+
+impl MulAssign for LHRowMajorMatrix64 {
+    fn mul_assign(&mut self, rhs: LHRowMajorMatrix64) {
+        *self = *self * rhs;
+    }
+}
+
+impl From<[f64; 4 * 4]> for LHRowMajorMatrix64 {
+    fn from(vals: [f64; 4 * 4]) -> LHRowMajorMatrix64 {
+        LHRowMajorMatrix64(vals)
+    }
+}
+
+impl From<LHRowMajorMatrix64> for [f64; 4 * 4] {
+    fn from(matrix: LHRowMajorMatrix64) -> [f64; 4 * 4] {
+        matrix.0
+    }
+}
+
+impl Index<(usize, usize)> for LHRowMajorMatrix64 {
+    type Output = f64;
+
+    fn index(&self, index: (usize, usize)) -> &Self::Output {
+        &self.0[index.0 + index.1 * 4]
+    }
+}
+
+impl IndexMut<(usize, usize)> for LHRowMajorMatrix64 {
+    fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
+        &mut self.0[index.0 + index.1 * 4]
+    }
+}
+
+impl std::fmt::Display for LHRowMajorMatrix64 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for i in 0..4 {
+            writeln!(
+                f,
+                "| {: <5} {: <5} {: <5} {: <5} |",
+                self.0[i * 4],
+                self.0[i * 4 + 1],
+                self.0[i * 4 + 2],
+                self.0[i * 4 + 3]
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alexandria_common::Vector4;
+
+    #[test]
+    fn matches_f32_matrix() {
+        let matrix64 = LHRowMajorMatrix64::translation(1.0, -2.0, 3.0)
+            * LHRowMajorMatrix64::rotation(0.3, -1.2, 2.5)
+            * LHRowMajorMatrix64::scale(2.0, 0.5, -4.0);
+        let matrix = LHRowMajorMatrix::translation(1.0, -2.0, 3.0)
+            * LHRowMajorMatrix::rotation(0.3, -1.2, 2.5)
+            * LHRowMajorMatrix::scale(2.0, 0.5, -4.0);
+
+        let inverse64 = matrix64.inverse().unwrap().to_f32();
+        let inverse = matrix.inverse().unwrap();
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((matrix64.to_f32().get(i, j) - matrix.get(i, j)).abs() < 1e-5);
+                assert!((inverse64.get(i, j) - inverse.get(i, j)).abs() < 1e-5);
+            }
+        }
+        assert!((matrix64.determinant() - matrix.determinant() as f64).abs() < 1e-4);
+
+        let vector = Vector4::new(1.0, 2.0, 3.0, 1.0);
+        let expected = [matrix.transform_vector(vector), matrix * vector];
+        let actual = [
+            matrix64.transform_vector([1.0, 2.0, 3.0, 1.0]),
+            matrix64 * [1.0, 2.0, 3.0, 1.0],
+        ];
+        for (actual, expected) in actual.iter().zip(expected) {
+            let expected = [expected.x(), expected.y(), expected.z(), expected.w()];
+            for i in 0..4 {
+                assert!((actual[i] - expected[i] as f64).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn camera_relative_keeps_precision_far_from_origin() {
+        let camera = [1.0e9, 2.0e9, -1.0e9];
+        let object = [camera[0] + 0.5, camera[1] + 0.25, camera[2] + 10.0];
+        let expected = [0.5, 0.25, 10.0];
+
+        // f32 has a spacing of 64 around 1e9, so the naive path loses the offset entirely
+        let naive_world =
+            LHRowMajorMatrix::translation(object[0] as f32, object[1] as f32, object[2] as f32);
+        let naive_view =
+            LHRowMajorMatrix::translation(-camera[0] as f32, -camera[1] as f32, -camera[2] as f32);
+        let naive = (naive_view * naive_world).transform_vector(Vector4::new(0.0, 0.0, 0.0, 1.0));
+
+        let world = LHRowMajorMatrix64::translation(object[0], object[1], object[2]);
+        let relative = world
+            .to_camera_relative(camera)
+            .transform_vector(Vector4::new(0.0, 0.0, 0.0, 1.0));
+
+        let naive = [naive.x(), naive.y(), naive.z()];
+        let relative = [relative.x(), relative.y(), relative.z()];
+        for i in 0..3 {
+            assert!((relative[i] - expected[i]).abs() < 1e-6);
+            assert!((naive[i] - expected[i]).abs() > 0.2);
+        }
+    }
+
+    #[test]
+    fn le_bytes_are_in_memory_order() {
+        let matrix = LHRowMajorMatrix64::translation(1.0, -2.0, 3.0)
+            * LHRowMajorMatrix64::rotation(0.3, -1.2, 2.5);
+        let bytes = matrix.to_le_bytes();
+
+        assert_eq!(bytes[0..8], matrix.get(0, 0).to_le_bytes());
+        assert_eq!(bytes[8..16], matrix.get(0, 1).to_le_bytes());
+        assert_eq!(bytes[24..32], 1.0f64.to_le_bytes());
+        assert_eq!(bytes[120..128], matrix.get(3, 3).to_le_bytes());
+        assert_eq!(
+            LHRowMajorMatrix64::from_le_bytes(bytes).to_le_bytes(),
+            bytes
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips() {
+        let matrix = LHRowMajorMatrix64::translation(1.0, -2.0, 3.0)
+            * LHRowMajorMatrix64::rotation(0.3, -1.2, 2.5);
+        let json = serde_json::to_string(&matrix).unwrap();
+        let decoded: LHRowMajorMatrix64 = serde_json::from_str(&json).unwrap();
+
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!((decoded.get(i, j) - matrix.get(i, j)).abs() < 1e-12);
+            }
+        }
+    }
+}
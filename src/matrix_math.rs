@@ -0,0 +1,296 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// The math shared by LHRowMajorMatrix and LHRowMajorMatrix64, on row-major arrays where
+// element (row, column) is at row * 4 + column
+pub(crate) trait Scalar:
+    Copy
+    + PartialEq
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const TWO: Self;
+
+    fn sqrt(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn tan(self) -> Self;
+    fn is_finite(self) -> bool;
+}
+
+macro_rules! impl_scalar {
+    ($($ty:ty),*) => {
+        $(
+            impl Scalar for $ty {
+                const ZERO: Self = 0.0;
+                const ONE: Self = 1.0;
+                const TWO: Self = 2.0;
+
+                fn sqrt(self) -> Self {
+                    <$ty>::sqrt(self)
+                }
+
+                fn sin(self) -> Self {
+                    <$ty>::sin(self)
+                }
+
+                fn cos(self) -> Self {
+                    <$ty>::cos(self)
+                }
+
+                fn tan(self) -> Self {
+                    <$ty>::tan(self)
+                }
+
+                fn is_finite(self) -> bool {
+                    <$ty>::is_finite(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar!(f32, f64);
+
+fn dot<T: Scalar>(a: [T; 3], b: [T; 3]) -> T {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross<T: Scalar>(a: [T; 3], b: [T; 3]) -> [T; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normal<T: Scalar>(a: [T; 3]) -> [T; 3] {
+    let length = dot(a, a).sqrt();
+    [a[0] / length, a[1] / length, a[2] / length]
+}
+
+pub(crate) fn identity<T: Scalar>() -> [T; 4 * 4] {
+    let mut matrix = [T::ZERO; 4 * 4];
+    for i in 0..4 {
+        matrix[i * 4 + i] = T::ONE;
+    }
+    matrix
+}
+
+pub(crate) fn look_at<T: Scalar>(position: [T; 3], target: [T; 3], up: [T; 3]) -> [T; 4 * 4] {
+    let z_axis = normal([
+        target[0] - position[0],
+        target[1] - position[1],
+        target[2] - position[2],
+    ]);
+    let x_axis = normal(cross(up, z_axis));
+    let y_axis = cross(z_axis, x_axis);
+
+    let mut matrix = [T::ZERO; 4 * 4];
+    for (i, axis) in [x_axis, y_axis, z_axis].into_iter().enumerate() {
+        matrix[i * 4] = axis[0];
+        matrix[i * 4 + 1] = axis[1];
+        matrix[i * 4 + 2] = axis[2];
+        matrix[i * 4 + 3] = -dot(axis, position);
+    }
+    matrix[15] = T::ONE;
+    matrix
+}
+
+pub(crate) fn scale<T: Scalar>(x: T, y: T, z: T) -> [T; 4 * 4] {
+    let mut matrix = identity();
+    matrix[0] = x;
+    matrix[5] = y;
+    matrix[10] = z;
+    matrix
+}
+
+pub(crate) fn translation<T: Scalar>(x: T, y: T, z: T) -> [T; 4 * 4] {
+    let mut matrix = identity();
+    matrix[3] = x;
+    matrix[7] = y;
+    matrix[11] = z;
+    matrix
+}
+
+pub(crate) fn rotation<T: Scalar>(x: T, y: T, z: T) -> [T; 4 * 4] {
+    let mut matrix = identity();
+
+    let cos_a = z.cos();
+    let sin_a = z.sin();
+    let cos_b = y.cos();
+    let sin_b = y.sin();
+    let cos_g = x.cos();
+    let sin_g = x.sin();
+
+    matrix[0] = cos_a * cos_b;
+    matrix[1] = sin_a * cos_b;
+    matrix[2] = -sin_b;
+
+    matrix[4] = cos_a * sin_b * sin_g - sin_a * cos_g;
+    matrix[5] = sin_a * sin_b * sin_g + cos_a * cos_g;
+    matrix[6] = cos_b * sin_g;
+
+    matrix[8] = cos_a * sin_b * cos_g + sin_a * sin_g;
+    matrix[9] = sin_a * sin_b * cos_g - cos_a * sin_g;
+    matrix[10] = cos_b * cos_g;
+
+    matrix
+}
+
+// Rotates about the axis whose row and column are left untouched. a and b are the indices of
+// the other two axes, in the order that keeps the rotation left handed.
+fn axis_rotation<T: Scalar>(angle: T, a: usize, b: usize) -> [T; 4 * 4] {
+    let mut matrix = identity();
+
+    let c = angle.cos();
+    let s = angle.sin();
+
+    matrix[a * 4 + a] = c;
+    matrix[b * 4 + a] = -s;
+    matrix[a * 4 + b] = s;
+    matrix[b * 4 + b] = c;
+
+    matrix
+}
+
+pub(crate) fn rotation_x<T: Scalar>(angle: T) -> [T; 4 * 4] {
+    axis_rotation(angle, 1, 2)
+}
+
+pub(crate) fn rotation_y<T: Scalar>(angle: T) -> [T; 4 * 4] {
+    axis_rotation(angle, 2, 0)
+}
+
+pub(crate) fn rotation_z<T: Scalar>(angle: T) -> [T; 4 * 4] {
+    axis_rotation(angle, 0, 1)
+}
+
+pub(crate) fn orthographic<T: Scalar>(width: T, height: T, near: T, far: T) -> [T; 4 * 4] {
+    let mut matrix = identity();
+    matrix[0] = T::TWO / width;
+    matrix[5] = T::TWO / height;
+    matrix[10] = T::ONE / (far - near);
+    matrix[11] = -near / (far - near);
+    matrix
+}
+
+pub(crate) fn perspective<T: Scalar>(fovy: T, aspect: T, near: T, far: T) -> [T; 4 * 4] {
+    let y_scale = T::ONE / (fovy / T::TWO).tan();
+    let x_scale = y_scale / aspect;
+
+    let mut matrix = [T::ZERO; 4 * 4];
+    matrix[0] = x_scale;
+    matrix[5] = y_scale;
+    matrix[10] = far / (far - near);
+    matrix[11] = -(near * far) / (far - near);
+    matrix[14] = T::ONE;
+    matrix
+}
+
+pub(crate) fn transpose<T: Scalar>(m: &[T; 4 * 4]) -> [T; 4 * 4] {
+    let mut matrix = *m;
+    for i in 0..4 {
+        for j in 0..4 {
+            matrix[i * 4 + j] = m[j * 4 + i];
+        }
+    }
+    matrix
+}
+
+// The 2x2 minors of the top two and bottom two rows, and the determinant expanded from them,
+// shared by determinant and inverse
+pub(crate) fn cofactors<T: Scalar>(m: &[T; 4 * 4]) -> ([T; 6], [T; 6], T) {
+    let s = [
+        m[0] * m[5] - m[4] * m[1],
+        m[0] * m[6] - m[4] * m[2],
+        m[0] * m[7] - m[4] * m[3],
+        m[1] * m[6] - m[5] * m[2],
+        m[1] * m[7] - m[5] * m[3],
+        m[2] * m[7] - m[6] * m[3],
+    ];
+
+    let c = [
+        m[8] * m[13] - m[12] * m[9],
+        m[8] * m[14] - m[12] * m[10],
+        m[8] * m[15] - m[12] * m[11],
+        m[9] * m[14] - m[13] * m[10],
+        m[9] * m[15] - m[13] * m[11],
+        m[10] * m[15] - m[14] * m[11],
+    ];
+
+    let determinant =
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+
+    (s, c, determinant)
+}
+
+pub(crate) fn inverse<T: Scalar>(m: &[T; 4 * 4]) -> Option<[T; 4 * 4]> {
+    let ([s0, s1, s2, s3, s4, s5], [c0, c1, c2, c3, c4, c5], determinant) = cofactors(m);
+    if determinant == T::ZERO || !determinant.is_finite() {
+        return None;
+    }
+
+    let inv_det = T::ONE / determinant;
+
+    Some([
+        (m[5] * c5 - m[6] * c4 + m[7] * c3) * inv_det,
+        (-m[1] * c5 + m[2] * c4 - m[3] * c3) * inv_det,
+        (m[13] * s5 - m[14] * s4 + m[15] * s3) * inv_det,
+        (-m[9] * s5 + m[10] * s4 - m[11] * s3) * inv_det,
+        (-m[4] * c5 + m[6] * c2 - m[7] * c1) * inv_det,
+        (m[0] * c5 - m[2] * c2 + m[3] * c1) * inv_det,
+        (-m[12] * s5 + m[14] * s2 - m[15] * s1) * inv_det,
+        (m[8] * s5 - m[10] * s2 + m[11] * s1) * inv_det,
+        (m[4] * c4 - m[5] * c2 + m[7] * c0) * inv_det,
+        (-m[0] * c4 + m[1] * c2 - m[3] * c0) * inv_det,
+        (m[12] * s4 - m[13] * s2 + m[15] * s0) * inv_det,
+        (-m[8] * s4 + m[9] * s2 - m[11] * s0) * inv_det,
+        (-m[4] * c3 + m[5] * c1 - m[6] * c0) * inv_det,
+        (m[0] * c3 - m[1] * c1 + m[2] * c0) * inv_det,
+        (-m[12] * s3 + m[13] * s1 - m[14] * s0) * inv_det,
+        (m[8] * s3 - m[9] * s1 + m[10] * s0) * inv_det,
+    ])
+}
+
+pub(crate) fn add<T: Scalar>(mut lhs: [T; 4 * 4], rhs: &[T; 4 * 4]) -> [T; 4 * 4] {
+    for (lhs, rhs) in lhs.iter_mut().zip(rhs.iter()) {
+        *lhs = *lhs + *rhs;
+    }
+    lhs
+}
+
+pub(crate) fn sub<T: Scalar>(mut lhs: [T; 4 * 4], rhs: &[T; 4 * 4]) -> [T; 4 * 4] {
+    for (lhs, rhs) in lhs.iter_mut().zip(rhs.iter()) {
+        *lhs = *lhs - *rhs;
+    }
+    lhs
+}
+
+pub(crate) fn multiply<T: Scalar>(lhs: &[T; 4 * 4], rhs: &[T; 4 * 4]) -> [T; 4 * 4] {
+    let mut ret = [T::ZERO; 4 * 4];
+    for i in 0..4 {
+        for j in 0..4 {
+            ret[i * 4 + j] = lhs[i * 4] * rhs[j]
+                + lhs[i * 4 + 1] * rhs[4 + j]
+                + lhs[i * 4 + 2] * rhs[8 + j]
+                + lhs[i * 4 + 3] * rhs[12 + j];
+        }
+    }
+    ret
+}
+
+pub(crate) fn transform<T: Scalar>(matrix: &[T; 4 * 4], vector: [T; 4]) -> [T; 4] {
+    let mut ret = [T::ZERO; 4];
+    for (i, value) in ret.iter_mut().enumerate() {
+        *value = matrix[i * 4] * vector[0]
+            + matrix[i * 4 + 1] * vector[1]
+            + matrix[i * 4 + 2] * vector[2]
+            + matrix[i * 4 + 3] * vector[3];
+    }
+    ret
+}
@@ -0,0 +1,619 @@
+use crate::LHRowMajorMatrix;
+use alexandria_common::{Matrix, Vector3, Vector4};
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Sub, SubAssign};
+
+// All variants share the logical indexing of LHRowMajorMatrix, so get(0, 3) is always the x
+// translation. Only the handedness of the constructors and the memory layout differ.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct RHRowMajorMatrix([f32; 4 * 4]);
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct LHColumnMajorMatrix([f32; 4 * 4]);
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct RHColumnMajorMatrix([f32; 4 * 4]);
+
+// The memory layout shaders expect in a constant buffer, regardless of which variant produced it
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct HLSLMatrix([f32; 4 * 4]);
+
+const fn row_major_index(col: usize, row: usize) -> usize {
+    col * 4 + row
+}
+
+const fn column_major_index(col: usize, row: usize) -> usize {
+    row * 4 + col
+}
+
+fn transpose_memory(vals: &[f32; 4 * 4]) -> [f32; 4 * 4] {
+    let mut ret = [0.0; 4 * 4];
+    for i in 0..4 {
+        for j in 0..4 {
+            ret[j * 4 + i] = vals[i * 4 + j];
+        }
+    }
+    ret
+}
+
+// Conjugates by a z flip, converting a transform between left and right handed coordinates.
+// Projection matrices map into clip space and should be rebuilt with the other variant's
+// constructor instead.
+fn flip_handedness<M: Matrix + Copy>(matrix: &M) -> M {
+    let mut ret = *matrix;
+    for i in 0..4 {
+        if i != 2 {
+            ret.set(i, 2, -matrix.get(i, 2));
+            ret.set(2, i, -matrix.get(2, i));
+        }
+    }
+    ret
+}
+
+macro_rules! impl_matrix_ops {
+    ($name: ident, $index: ident) => {
+        impl $name {
+            // Transforms a column vector, computing M * v like
+            // LHRowMajorMatrix::transform_vector
+            pub fn transform_vector(&self, vector: Vector4) -> Vector4 {
+                let vector = [vector.x(), vector.y(), vector.z(), vector.w()];
+                let mut ret = [0.0; 4];
+                for (i, value) in ret.iter_mut().enumerate() {
+                    *value = self.get(i, 0) * vector[0]
+                        + self.get(i, 1) * vector[1]
+                        + self.get(i, 2) * vector[2]
+                        + self.get(i, 3) * vector[3];
+                }
+
+                Vector4::new(ret[0], ret[1], ret[2], ret[3])
+            }
+
+            pub fn transpose(&self) -> $name {
+                let mut matrix = $name::zero();
+                for i in 0..4 {
+                    for j in 0..4 {
+                        matrix.set(i, j, self.get(j, i));
+                    }
+                }
+                matrix
+            }
+        }
+
+        #[cfg(feature = "bytemuck")]
+        unsafe impl bytemuck::Zeroable for $name {}
+
+        #[cfg(feature = "bytemuck")]
+        unsafe impl bytemuck::Pod for $name {}
+
+        impl Add for $name {
+            type Output = $name;
+
+            fn add(mut self, rhs: $name) -> $name {
+                for i in 0..4 * 4 {
+                    self.0[i] += rhs.0[i];
+                }
+
+                self
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: $name) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+
+            fn sub(mut self, rhs: $name) -> $name {
+                for i in 0..4 * 4 {
+                    self.0[i] -= rhs.0[i];
+                }
+
+                self
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: $name) {
+                *self = *self - rhs;
+            }
+        }
+
+        // Treats the vector as a row, computing v * M like LHRowMajorMatrix does
+        impl Mul<Vector4> for $name {
+            type Output = Vector4;
+
+            fn mul(self, rhs: Vector4) -> Vector4 {
+                self.transpose().transform_vector(rhs)
+            }
+        }
+
+        impl Mul for $name {
+            type Output = $name;
+
+            fn mul(self, rhs: $name) -> $name {
+                let mut ret = $name::zero();
+                for i in 0..4 {
+                    for j in 0..4 {
+                        ret.set(
+                            i,
+                            j,
+                            self.get(i, 0) * rhs.get(0, j)
+                                + self.get(i, 1) * rhs.get(1, j)
+                                + self.get(i, 2) * rhs.get(2, j)
+                                + self.get(i, 3) * rhs.get(3, j),
+                        );
+                    }
+                }
+
+                ret
+            }
+        }
+
+        impl MulAssign for $name {
+            fn mul_assign(&mut self, rhs: $name) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl From<[f32; 4 * 4]> for $name {
+            fn from(vals: [f32; 4 * 4]) -> $name {
+                $name(vals)
+            }
+        }
+
+        impl From<$name> for [f32; 4 * 4] {
+            fn from(matrix: $name) -> [f32; 4 * 4] {
+                matrix.0
+            }
+        }
+
+        impl Index<(usize, usize)> for $name {
+            type Output = f32;
+
+            fn index(&self, index: (usize, usize)) -> &Self::Output {
+                &self.0[$index(index.1, index.0)]
+            }
+        }
+
+        impl IndexMut<(usize, usize)> for $name {
+            fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
+                &mut self.0[$index(index.1, index.0)]
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                for i in 0..4 {
+                    writeln!(
+                        f,
+                        "| {: <5} {: <5} {: <5} {: <5} |",
+                        self.get(i, 0),
+                        self.get(i, 1),
+                        self.get(i, 2),
+                        self.get(i, 3)
+                    )?;
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+
+// Implements Matrix for a column major variant by transposing the row major constructors
+macro_rules! impl_column_major_matrix {
+    ($name: ident, $row_major: ident) => {
+        impl Matrix for $name {
+            fn zero() -> Self {
+                $name([0.0; 4 * 4])
+            }
+
+            fn identity() -> Self {
+                $row_major::identity().into()
+            }
+
+            fn look_at(position: Vector3, target: Vector3, up: Vector3) -> Self {
+                $row_major::look_at(position, target, up).into()
+            }
+
+            fn scale(x: f32, y: f32, z: f32) -> Self {
+                $row_major::scale(x, y, z).into()
+            }
+
+            fn translation(x: f32, y: f32, z: f32) -> Self {
+                $row_major::translation(x, y, z).into()
+            }
+
+            fn rotation(x: f32, y: f32, z: f32) -> Self {
+                $row_major::rotation(x, y, z).into()
+            }
+
+            fn rotation_x(angle: f32) -> Self {
+                $row_major::rotation_x(angle).into()
+            }
+
+            fn rotation_y(angle: f32) -> Self {
+                $row_major::rotation_y(angle).into()
+            }
+
+            fn rotation_z(angle: f32) -> Self {
+                $row_major::rotation_z(angle).into()
+            }
+
+            fn orthographic(width: f32, height: f32, near: f32, far: f32) -> Self {
+                $row_major::orthographic(width, height, near, far).into()
+            }
+
+            fn perspective(fovy: f32, aspect: f32, near: f32, far: f32) -> Self {
+                $row_major::perspective(fovy, aspect, near, far).into()
+            }
+
+            fn get(&self, col: usize, row: usize) -> f32 {
+                self.0[column_major_index(col, row)]
+            }
+
+            fn set(&mut self, col: usize, row: usize, val: f32) {
+                self.0[column_major_index(col, row)] = val
+            }
+        }
+
+        impl From<$row_major> for $name {
+            fn from(matrix: $row_major) -> $name {
+                $name(transpose_memory(&matrix.into()))
+            }
+        }
+
+        impl From<$name> for $row_major {
+            fn from(matrix: $name) -> $row_major {
+                transpose_memory(&matrix.0).into()
+            }
+        }
+
+        impl From<$name> for HLSLMatrix {
+            fn from(matrix: $name) -> HLSLMatrix {
+                HLSLMatrix(transpose_memory(&matrix.0))
+            }
+        }
+    };
+}
+
+impl_matrix_ops!(RHRowMajorMatrix, row_major_index);
+impl_matrix_ops!(LHColumnMajorMatrix, column_major_index);
+impl_matrix_ops!(RHColumnMajorMatrix, column_major_index);
+
+impl_column_major_matrix!(LHColumnMajorMatrix, LHRowMajorMatrix);
+impl_column_major_matrix!(RHColumnMajorMatrix, RHRowMajorMatrix);
+
+impl Matrix for RHRowMajorMatrix {
+    fn zero() -> Self {
+        RHRowMajorMatrix([0.0; 4 * 4])
+    }
+
+    fn identity() -> Self {
+        RHRowMajorMatrix(LHRowMajorMatrix::identity().into())
+    }
+
+    fn look_at(position: Vector3, target: Vector3, up: Vector3) -> Self {
+        let z_axis = (position - target).normal();
+        let x_axis = (up.cross(z_axis)).normal();
+        let y_axis = z_axis.cross(x_axis);
+
+        let mut matrix = RHRowMajorMatrix::zero();
+        matrix.set(0, 0, x_axis.x());
+        matrix.set(0, 1, x_axis.y());
+        matrix.set(0, 2, x_axis.z());
+        matrix.set(0, 3, -x_axis.dot(position));
+        matrix.set(1, 0, y_axis.x());
+        matrix.set(1, 1, y_axis.y());
+        matrix.set(1, 2, y_axis.z());
+        matrix.set(1, 3, -y_axis.dot(position));
+        matrix.set(2, 0, z_axis.x());
+        matrix.set(2, 1, z_axis.y());
+        matrix.set(2, 2, z_axis.z());
+        matrix.set(2, 3, -z_axis.dot(position));
+        matrix.set(3, 3, 1.0);
+        matrix
+    }
+
+    fn scale(x: f32, y: f32, z: f32) -> Self {
+        RHRowMajorMatrix(LHRowMajorMatrix::scale(x, y, z).into())
+    }
+
+    fn translation(x: f32, y: f32, z: f32) -> Self {
+        RHRowMajorMatrix(LHRowMajorMatrix::translation(x, y, z).into())
+    }
+
+    fn rotation(x: f32, y: f32, z: f32) -> Self {
+        RHRowMajorMatrix(LHRowMajorMatrix::rotation(x, y, z).into())
+    }
+
+    fn rotation_x(angle: f32) -> Self {
+        RHRowMajorMatrix(LHRowMajorMatrix::rotation_x(angle).into())
+    }
+
+    fn rotation_y(angle: f32) -> Self {
+        RHRowMajorMatrix(LHRowMajorMatrix::rotation_y(angle).into())
+    }
+
+    fn rotation_z(angle: f32) -> Self {
+        RHRowMajorMatrix(LHRowMajorMatrix::rotation_z(angle).into())
+    }
+
+    fn orthographic(width: f32, height: f32, near: f32, far: f32) -> Self {
+        let mut matrix = RHRowMajorMatrix::identity();
+        matrix.set(0, 0, 2.0 / width);
+        matrix.set(1, 1, 2.0 / height);
+        matrix.set(2, 2, 1.0 / (near - far));
+        matrix.set(2, 3, near / (near - far));
+        matrix
+    }
+
+    fn perspective(fovy: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let y_scale = 1.0 / (fovy / 2.0).tan();
+        let x_scale = y_scale / aspect;
+
+        let mut matrix = RHRowMajorMatrix::zero();
+        matrix.set(0, 0, x_scale);
+        matrix.set(1, 1, y_scale);
+        matrix.set(2, 2, far / (near - far));
+        matrix.set(2, 3, (near * far) / (near - far));
+        matrix.set(3, 2, -1.0);
+        matrix
+    }
+
+    fn get(&self, col: usize, row: usize) -> f32 {
+        self.0[row_major_index(col, row)]
+    }
+
+    fn set(&mut self, col: usize, row: usize, val: f32) {
+        self.0[row_major_index(col, row)] = val
+    }
+}
+
+impl LHRowMajorMatrix {
+    pub fn to_right_handed(&self) -> RHRowMajorMatrix {
+        let flipped: [f32; 4 * 4] = flip_handedness(self).into();
+        flipped.into()
+    }
+
+    pub fn to_column_major(&self) -> LHColumnMajorMatrix {
+        (*self).into()
+    }
+}
+
+impl RHRowMajorMatrix {
+    pub fn to_left_handed(&self) -> LHRowMajorMatrix {
+        flip_handedness(self).0.into()
+    }
+
+    pub fn to_column_major(&self) -> RHColumnMajorMatrix {
+        (*self).into()
+    }
+}
+
+impl LHColumnMajorMatrix {
+    pub fn to_right_handed(&self) -> RHColumnMajorMatrix {
+        RHColumnMajorMatrix(flip_handedness(self).0)
+    }
+
+    pub fn to_row_major(&self) -> LHRowMajorMatrix {
+        (*self).into()
+    }
+}
+
+impl RHColumnMajorMatrix {
+    pub fn to_left_handed(&self) -> LHColumnMajorMatrix {
+        LHColumnMajorMatrix(flip_handedness(self).0)
+    }
+
+    pub fn to_row_major(&self) -> RHRowMajorMatrix {
+        (*self).into()
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for HLSLMatrix {}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for HLSLMatrix {}
+
+impl From<LHRowMajorMatrix> for HLSLMatrix {
+    fn from(matrix: LHRowMajorMatrix) -> HLSLMatrix {
+        HLSLMatrix(matrix.into())
+    }
+}
+
+impl From<RHRowMajorMatrix> for HLSLMatrix {
+    fn from(matrix: RHRowMajorMatrix) -> HLSLMatrix {
+        HLSLMatrix(matrix.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_vector_eq(actual: Vector4, expected: [f32; 4]) {
+        let actual = [actual.x(), actual.y(), actual.z(), actual.w()];
+        for (actual_value, expected_value) in actual.iter().zip(expected) {
+            assert!(
+                (actual_value - expected_value).abs() < EPSILON,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    fn memory<M: Into<[f32; 16]>>(matrix: M) -> [f32; 16] {
+        matrix.into()
+    }
+
+    fn transform() -> LHRowMajorMatrix {
+        LHRowMajorMatrix::translation(1.0, -2.0, 3.0)
+            * LHRowMajorMatrix::rotation(0.3, -1.2, 2.5)
+            * LHRowMajorMatrix::scale(2.0, 0.5, 4.0)
+    }
+
+    // Divides a projected point by w
+    fn divide(point: Vector4) -> Vector4 {
+        Vector4::new(
+            point.x() / point.w(),
+            point.y() / point.w(),
+            point.z() / point.w(),
+            1.0,
+        )
+    }
+
+    #[test]
+    fn handedness_round_trips() {
+        let left = transform();
+        let right = left.to_right_handed();
+        assert_eq!(memory(right.to_left_handed()), memory(left));
+
+        let column = left.to_column_major();
+        assert_eq!(
+            memory(column.to_right_handed().to_left_handed()),
+            memory(column)
+        );
+    }
+
+    #[test]
+    fn flipping_handedness_mirrors_z() {
+        let left = transform();
+        let right = left.to_right_handed();
+
+        // Mirroring a point, transforming it with the right handed matrix and mirroring the
+        // result back matches the left handed transform
+        let (x, y, z) = (0.5, -1.5, 2.0);
+        let expected = left.transform_vector(Vector4::new(x, y, z, 1.0));
+        let mirrored = right.transform_vector(Vector4::new(x, y, -z, 1.0));
+        assert_vector_eq(
+            Vector4::new(mirrored.x(), mirrored.y(), -mirrored.z(), mirrored.w()),
+            [expected.x(), expected.y(), expected.z(), expected.w()],
+        );
+
+        let translation = LHRowMajorMatrix::translation(1.0, 2.0, 3.0).to_right_handed();
+        assert_eq!(translation.get(0, 3), 1.0);
+        assert_eq!(translation.get(1, 3), 2.0);
+        assert_eq!(translation.get(2, 3), -3.0);
+    }
+
+    #[test]
+    fn majorness_round_trips() {
+        let row = transform();
+        let column = row.to_column_major();
+        assert_eq!(memory(column.to_row_major()), memory(row));
+
+        // The logical elements stay put while the memory is transposed
+        let row_memory = memory(row);
+        let column_memory = memory(column);
+        for i in 0..4 {
+            for j in 0..4 {
+                assert_eq!(column.get(i, j), row.get(i, j));
+                assert_eq!(column_memory[j * 4 + i], row_memory[i * 4 + j]);
+            }
+        }
+
+        let right = row.to_right_handed();
+        assert_eq!(
+            memory(right.to_column_major().to_row_major()),
+            memory(right)
+        );
+    }
+
+    #[test]
+    fn hlsl_matrix_is_row_major_memory() {
+        let row = transform();
+        let expected = memory(row);
+        assert_eq!(expected[3], 1.0);
+
+        assert_eq!(HLSLMatrix::from(row).0, expected);
+        assert_eq!(HLSLMatrix::from(row.to_column_major()).0, expected);
+
+        let right = row.to_right_handed();
+        let expected = memory(right);
+        assert_eq!(HLSLMatrix::from(right).0, expected);
+        assert_eq!(HLSLMatrix::from(right.to_column_major()).0, expected);
+    }
+
+    #[test]
+    fn right_handed_perspective_maps_negative_z_to_depth() {
+        let (near, far) = (0.5, 100.0);
+        let projection = RHRowMajorMatrix::perspective(std::f32::consts::FRAC_PI_2, 2.0, near, far);
+
+        assert_vector_eq(
+            divide(projection.transform_vector(Vector4::new(0.0, 0.0, -near, 1.0))),
+            [0.0, 0.0, 0.0, 1.0],
+        );
+        assert_vector_eq(
+            divide(projection.transform_vector(Vector4::new(0.0, 0.0, -far, 1.0))),
+            [0.0, 0.0, 1.0, 1.0],
+        );
+
+        // w is the distance in front of the camera, and the corners of the view land on the
+        // edges of clip space
+        let center = projection.transform_vector(Vector4::new(0.0, 0.0, -10.0, 1.0));
+        assert_eq!(center.w(), 10.0);
+        assert_vector_eq(
+            divide(projection.transform_vector(Vector4::new(20.0, 10.0, -10.0, 1.0))),
+            [1.0, 1.0, divide(center).z(), 1.0],
+        );
+
+        let column = RHColumnMajorMatrix::perspective(std::f32::consts::FRAC_PI_2, 2.0, near, far);
+        assert_vector_eq(
+            divide(column.transform_vector(Vector4::new(0.0, 0.0, -far, 1.0))),
+            [0.0, 0.0, 1.0, 1.0],
+        );
+    }
+
+    #[test]
+    fn right_handed_orthographic_maps_negative_z_to_depth() {
+        let projection = RHRowMajorMatrix::orthographic(4.0, 2.0, 1.0, 11.0);
+
+        assert_vector_eq(
+            divide(projection.transform_vector(Vector4::new(2.0, 1.0, -1.0, 1.0))),
+            [1.0, 1.0, 0.0, 1.0],
+        );
+        assert_vector_eq(
+            divide(projection.transform_vector(Vector4::new(-2.0, -1.0, -11.0, 1.0))),
+            [-1.0, -1.0, 1.0, 1.0],
+        );
+        assert_vector_eq(
+            divide(projection.transform_vector(Vector4::new(0.0, 0.0, -6.0, 1.0))),
+            [0.0, 0.0, 0.5, 1.0],
+        );
+    }
+
+    #[test]
+    fn right_handed_look_at_faces_negative_z() {
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        let view = RHRowMajorMatrix::look_at(
+            Vector3::new(1.0, 2.0, 3.0),
+            Vector3::new(1.0, 2.0, -7.0),
+            up,
+        );
+
+        // The target ends up straight ahead, 10 units down the negative z axis
+        assert_vector_eq(
+            view.transform_vector(Vector4::new(1.0, 2.0, -7.0, 1.0)),
+            [0.0, 0.0, -10.0, 1.0],
+        );
+        assert_vector_eq(
+            view.transform_vector(Vector4::new(2.0, 3.0, 3.0, 1.0)),
+            [1.0, 1.0, 0.0, 1.0],
+        );
+    }
+}
@@ -0,0 +1,415 @@
+use crate::LHRowMajorMatrix;
+use alexandria_common::{Matrix, Vector3};
+use std::ops::{Mul, MulAssign, Neg};
+
+// Rotations follow the same convention as LHRowMajorMatrix, so
+// Quaternion::from_axis_angle(x_axis, a).to_matrix() == LHRowMajorMatrix::rotation_x(a)
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
+pub struct Quaternion {
+    x: f32,
+    y: f32,
+    z: f32,
+    w: f32,
+}
+
+const NLERP_THRESHOLD: f32 = 0.9995;
+
+impl Quaternion {
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Quaternion { x, y, z, w }
+    }
+
+    pub const fn identity() -> Self {
+        Quaternion::new(0.0, 0.0, 0.0, 1.0)
+    }
+
+    pub fn from_axis_angle(axis: Vector3, angle: f32) -> Self {
+        let axis = axis.normal();
+        let (sin, cos) = (-angle / 2.0).sin_cos();
+        Quaternion::new(axis.x() * sin, axis.y() * sin, axis.z() * sin, cos)
+    }
+
+    pub fn rotation_x(angle: f32) -> Self {
+        let (sin, cos) = (-angle / 2.0).sin_cos();
+        Quaternion::new(sin, 0.0, 0.0, cos)
+    }
+
+    pub fn rotation_y(angle: f32) -> Self {
+        let (sin, cos) = (-angle / 2.0).sin_cos();
+        Quaternion::new(0.0, sin, 0.0, cos)
+    }
+
+    pub fn rotation_z(angle: f32) -> Self {
+        let (sin, cos) = (-angle / 2.0).sin_cos();
+        Quaternion::new(0.0, 0.0, sin, cos)
+    }
+
+    // Matches LHRowMajorMatrix::rotation(x, y, z)
+    pub fn from_euler(x: f32, y: f32, z: f32) -> Self {
+        Quaternion::rotation_x(x) * Quaternion::rotation_y(y) * Quaternion::rotation_z(z)
+    }
+
+    // Expects the upper 3x3 of the matrix to be a pure rotation
+    pub fn from_matrix(matrix: &LHRowMajorMatrix) -> Self {
+        let m00 = matrix.get(0, 0);
+        let m01 = matrix.get(0, 1);
+        let m02 = matrix.get(0, 2);
+        let m10 = matrix.get(1, 0);
+        let m11 = matrix.get(1, 1);
+        let m12 = matrix.get(1, 2);
+        let m20 = matrix.get(2, 0);
+        let m21 = matrix.get(2, 1);
+        let m22 = matrix.get(2, 2);
+
+        let trace = m00 + m11 + m22;
+        let quaternion = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quaternion::new((m21 - m12) / s, (m02 - m20) / s, (m10 - m01) / s, s / 4.0)
+        } else if m00 > m11 && m00 > m22 {
+            let s = (1.0 + m00 - m11 - m22).sqrt() * 2.0;
+            Quaternion::new(s / 4.0, (m01 + m10) / s, (m02 + m20) / s, (m21 - m12) / s)
+        } else if m11 > m22 {
+            let s = (1.0 + m11 - m00 - m22).sqrt() * 2.0;
+            Quaternion::new((m01 + m10) / s, s / 4.0, (m12 + m21) / s, (m02 - m20) / s)
+        } else {
+            let s = (1.0 + m22 - m00 - m11).sqrt() * 2.0;
+            Quaternion::new((m02 + m20) / s, (m12 + m21) / s, s / 4.0, (m10 - m01) / s)
+        };
+
+        quaternion.normal()
+    }
+
+    pub fn to_matrix(&self) -> LHRowMajorMatrix {
+        let (x, y, z, w) = (self.x, self.y, self.z, self.w);
+
+        let mut matrix = LHRowMajorMatrix::identity();
+        matrix.set(0, 0, 1.0 - 2.0 * (y * y + z * z));
+        matrix.set(0, 1, 2.0 * (x * y - w * z));
+        matrix.set(0, 2, 2.0 * (x * z + w * y));
+
+        matrix.set(1, 0, 2.0 * (x * y + w * z));
+        matrix.set(1, 1, 1.0 - 2.0 * (x * x + z * z));
+        matrix.set(1, 2, 2.0 * (y * z - w * x));
+
+        matrix.set(2, 0, 2.0 * (x * z - w * y));
+        matrix.set(2, 1, 2.0 * (y * z + w * x));
+        matrix.set(2, 2, 1.0 - 2.0 * (x * x + y * y));
+
+        matrix
+    }
+
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    pub fn z(&self) -> f32 {
+        self.z
+    }
+
+    pub fn w(&self) -> f32 {
+        self.w
+    }
+
+    pub fn dot(&self, rhs: Quaternion) -> f32 {
+        self.x * rhs.x + self.y * rhs.y + self.z * rhs.z + self.w * rhs.w
+    }
+
+    pub fn length(&self) -> f32 {
+        self.dot(*self).sqrt()
+    }
+
+    pub fn normal(&self) -> Quaternion {
+        let length = self.length();
+        if length == 0.0 {
+            return Quaternion::identity();
+        }
+
+        Quaternion::new(
+            self.x / length,
+            self.y / length,
+            self.z / length,
+            self.w / length,
+        )
+    }
+
+    pub fn conjugate(&self) -> Quaternion {
+        Quaternion::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    pub fn inverse(&self) -> Quaternion {
+        let length_squared = self.dot(*self);
+        if length_squared == 0.0 {
+            return Quaternion::identity();
+        }
+
+        let conjugate = self.conjugate();
+        Quaternion::new(
+            conjugate.x / length_squared,
+            conjugate.y / length_squared,
+            conjugate.z / length_squared,
+            conjugate.w / length_squared,
+        )
+    }
+
+    pub fn rotate(&self, vector: Vector3) -> Vector3 {
+        let (qx, qy, qz, w) = (self.x, self.y, self.z, self.w);
+        let (vx, vy, vz) = (vector.x(), vector.y(), vector.z());
+
+        // t = 2 * cross(q, v)
+        let tx = 2.0 * (qy * vz - qz * vy);
+        let ty = 2.0 * (qz * vx - qx * vz);
+        let tz = 2.0 * (qx * vy - qy * vx);
+
+        // v' = v + w * t + cross(q, t)
+        Vector3::new(
+            vx + w * tx + (qy * tz - qz * ty),
+            vy + w * ty + (qz * tx - qx * tz),
+            vz + w * tz + (qx * ty - qy * tx),
+        )
+    }
+
+    pub fn nlerp(&self, target: Quaternion, t: f32) -> Quaternion {
+        // Take the shortest path
+        let target = if self.dot(target) < 0.0 {
+            -target
+        } else {
+            target
+        };
+
+        Quaternion::new(
+            self.x + (target.x - self.x) * t,
+            self.y + (target.y - self.y) * t,
+            self.z + (target.z - self.z) * t,
+            self.w + (target.w - self.w) * t,
+        )
+        .normal()
+    }
+
+    pub fn slerp(&self, target: Quaternion, t: f32) -> Quaternion {
+        let mut cos_theta = self.dot(target);
+
+        // Take the shortest path
+        let target = if cos_theta < 0.0 {
+            cos_theta = -cos_theta;
+            -target
+        } else {
+            target
+        };
+
+        // Fall back to nlerp when the quaternions are nearly parallel
+        if cos_theta > NLERP_THRESHOLD {
+            return self.nlerp(target, t);
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / sin_theta;
+        let b = (t * theta).sin() / sin_theta;
+
+        Quaternion::new(
+            self.x * a + target.x * b,
+            self.y * a + target.y * b,
+            self.z * a + target.z * b,
+            self.w * a + target.w * b,
+        )
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for Quaternion {}
+
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for Quaternion {}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Quaternion::identity()
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+impl MulAssign for Quaternion {
+    fn mul_assign(&mut self, rhs: Quaternion) {
+        *self = *self * rhs;
+    }
+}
+
+impl Mul<Vector3> for Quaternion {
+    type Output = Vector3;
+
+    fn mul(self, rhs: Vector3) -> Vector3 {
+        self.rotate(rhs)
+    }
+}
+
+impl Neg for Quaternion {
+    type Output = Quaternion;
+
+    fn neg(self) -> Quaternion {
+        Quaternion::new(-self.x, -self.y, -self.z, -self.w)
+    }
+}
+
+impl From<Quaternion> for LHRowMajorMatrix {
+    fn from(quaternion: Quaternion) -> LHRowMajorMatrix {
+        quaternion.to_matrix()
+    }
+}
+
+impl std::fmt::Display for Quaternion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "({}, {}, {}, {})", self.x, self.y, self.z, self.w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alexandria_common::Vector4;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_rotation_eq(actual: &LHRowMajorMatrix, expected: &LHRowMajorMatrix) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert!(
+                    (actual.get(i, j) - expected.get(i, j)).abs() < EPSILON,
+                    "element ({}, {}) differs\n{}\n{}",
+                    i,
+                    j,
+                    actual,
+                    expected
+                );
+            }
+        }
+    }
+
+    // q and -q represent the same rotation
+    fn assert_quaternion_eq(actual: Quaternion, expected: Quaternion) {
+        assert!(
+            (actual.dot(expected).abs() - 1.0).abs() < EPSILON,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    fn assert_vector_eq(actual: Vector3, expected: Vector3) {
+        assert!(
+            (actual.x() - expected.x()).abs() < EPSILON
+                && (actual.y() - expected.y()).abs() < EPSILON
+                && (actual.z() - expected.z()).abs() < EPSILON,
+            "({}, {}, {}) != ({}, {}, {})",
+            actual.x(),
+            actual.y(),
+            actual.z(),
+            expected.x(),
+            expected.y(),
+            expected.z()
+        );
+    }
+
+    fn rotations() -> [Quaternion; 5] {
+        [
+            Quaternion::identity(),
+            Quaternion::rotation_x(0.7),
+            Quaternion::from_euler(0.3, -1.2, 2.5),
+            Quaternion::from_axis_angle(Vector3::new(1.0, 2.0, -3.0), 2.9),
+            // Near 180 degrees, where the trace of the matrix is negative
+            Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 1.0), 3.1),
+        ]
+    }
+
+    #[test]
+    fn matrix_round_trip() {
+        for rotation in rotations() {
+            assert_quaternion_eq(Quaternion::from_matrix(&rotation.to_matrix()), rotation);
+        }
+    }
+
+    #[test]
+    fn euler_matches_matrix_rotation() {
+        for (x, y, z) in [(0.3, -1.2, 2.5), (1.5, 0.0, -0.5), (-2.0, 1.0, 0.25)] {
+            assert_rotation_eq(
+                &Quaternion::from_euler(x, y, z).to_matrix(),
+                &LHRowMajorMatrix::rotation(x, y, z),
+            );
+        }
+    }
+
+    #[test]
+    fn axis_angle_matches_matrix_rotation() {
+        let angle = 0.9;
+        assert_rotation_eq(
+            &Quaternion::from_axis_angle(Vector3::new(1.0, 0.0, 0.0), angle).to_matrix(),
+            &LHRowMajorMatrix::rotation_x(angle),
+        );
+        assert_rotation_eq(
+            &Quaternion::from_axis_angle(Vector3::new(0.0, 1.0, 0.0), angle).to_matrix(),
+            &LHRowMajorMatrix::rotation_y(angle),
+        );
+        assert_rotation_eq(
+            &Quaternion::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), angle).to_matrix(),
+            &LHRowMajorMatrix::rotation_z(angle),
+        );
+
+        // The axis is left in place
+        let axis = Vector3::new(1.0, 2.0, -3.0);
+        assert_vector_eq(Quaternion::from_axis_angle(axis, angle).rotate(axis), axis);
+    }
+
+    #[test]
+    fn slerp_endpoints_and_shortest_path() {
+        let start = Quaternion::rotation_y(0.2);
+        let end = Quaternion::from_euler(0.5, 1.0, -0.4);
+
+        assert_quaternion_eq(start.slerp(end, 0.0), start);
+        assert_quaternion_eq(start.slerp(end, 1.0), end);
+        assert_quaternion_eq(start.nlerp(end, 0.0), start);
+        assert_quaternion_eq(start.nlerp(end, 1.0), end);
+
+        let half = Quaternion::identity().slerp(Quaternion::rotation_z(1.5), 0.5);
+        assert_quaternion_eq(half, Quaternion::rotation_z(0.75));
+
+        // -end is the same rotation, so it must not take the long way around
+        for t in [0.25, 0.5, 0.75] {
+            assert_quaternion_eq(start.slerp(-end, t), start.slerp(end, t));
+        }
+    }
+
+    #[test]
+    fn rotate_matches_matrix_transform() {
+        let vector = Vector3::new(1.0, -2.0, 0.5);
+        for rotation in rotations() {
+            let transformed = rotation.to_matrix().transform_vector(Vector4::new(
+                vector.x(),
+                vector.y(),
+                vector.z(),
+                0.0,
+            ));
+
+            assert_vector_eq(
+                rotation.rotate(vector),
+                Vector3::new(transformed.x(), transformed.y(), transformed.z()),
+            );
+        }
+    }
+}
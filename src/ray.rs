@@ -0,0 +1,377 @@
+use crate::{BoundingBox, BoundingSphere, LHRowMajorMatrix};
+use alexandria_common::{Vector2, Vector3, Vector4};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Ray {
+    origin: Vector3,
+    direction: Vector3,
+}
+
+const TRIANGLE_EPSILON: f32 = 1.0e-7;
+
+// Screen coordinates are in pixels from the top left of the window. The mouse position reported
+// to Input is relative to the center of the window, so convert it with cursor_to_screen first.
+fn screen_to_ndc(screen: Vector2, top_left: Vector2, size: Vector2) -> (f32, f32) {
+    (
+        (screen.x() - top_left.x()) / size.x() * 2.0 - 1.0,
+        1.0 - (screen.y() - top_left.y()) / size.y() * 2.0,
+    )
+}
+
+fn transform_point(matrix: &LHRowMajorMatrix, point: Vector4) -> Option<Vector3> {
+    let point = matrix.transform_vector(point);
+    if point.w() == 0.0 || !point.w().is_finite() {
+        return None;
+    }
+
+    Some(Vector3::new(
+        point.x() / point.w(),
+        point.y() / point.w(),
+        point.z() / point.w(),
+    ))
+}
+
+pub fn cursor_to_screen(cursor: (isize, isize), window_size: (usize, usize)) -> Vector2 {
+    Vector2::new(
+        (cursor.0 + window_size.0 as isize / 2) as f32,
+        (cursor.1 + window_size.1 as isize / 2) as f32,
+    )
+}
+
+// Returns the screen position of a world space point in x and y, and its depth in z. Points
+// behind the camera have no screen position.
+pub fn project(
+    point: Vector3,
+    top_left: Vector2,
+    size: Vector2,
+    view: &LHRowMajorMatrix,
+    projection: &LHRowMajorMatrix,
+) -> Option<Vector3> {
+    let clip =
+        (*projection * *view).transform_vector(Vector4::new(point.x(), point.y(), point.z(), 1.0));
+    if clip.w() <= 0.0 {
+        return None;
+    }
+
+    let x = clip.x() / clip.w();
+    let y = clip.y() / clip.w();
+    Some(Vector3::new(
+        top_left.x() + (x + 1.0) / 2.0 * size.x(),
+        top_left.y() + (1.0 - y) / 2.0 * size.y(),
+        clip.z() / clip.w(),
+    ))
+}
+
+// The inverse of project, taking the screen position in x and y and the depth in z
+pub fn unproject(
+    screen: Vector3,
+    top_left: Vector2,
+    size: Vector2,
+    view: &LHRowMajorMatrix,
+    projection: &LHRowMajorMatrix,
+) -> Option<Vector3> {
+    let inverse = (*projection * *view).inverse()?;
+    let (x, y) = screen_to_ndc(Vector2::new(screen.x(), screen.y()), top_left, size);
+    transform_point(&inverse, Vector4::new(x, y, screen.z(), 1.0))
+}
+
+impl Ray {
+    pub fn new(origin: Vector3, direction: Vector3) -> Self {
+        Ray {
+            origin,
+            direction: direction.normal(),
+        }
+    }
+
+    // Builds the world space ray passing through a screen position. The ray starts on the
+    // camera plane, so it works for perspective and orthographic projections alike.
+    pub fn from_screen(
+        screen: Vector2,
+        top_left: Vector2,
+        size: Vector2,
+        view: &LHRowMajorMatrix,
+        projection: &LHRowMajorMatrix,
+    ) -> Option<Self> {
+        let inverse_projection = projection.inverse()?;
+        let inverse_view = view.inverse_affine()?;
+        let (x, y) = screen_to_ndc(screen, top_left, size);
+
+        // Two points along the ray in view space. These depths are finite for every projection,
+        // including the infinite and reversed-Z ones.
+        let a = transform_point(&inverse_projection, Vector4::new(x, y, 0.25, 1.0))?;
+        let b = transform_point(&inverse_projection, Vector4::new(x, y, 0.75, 1.0))?;
+
+        // Point the ray away from the camera
+        let direction = if b.z() >= a.z() { b - a } else { a - b };
+        if direction.z() == 0.0 {
+            return None;
+        }
+
+        // Move the origin back to the camera plane
+        let t = a.z() / direction.z();
+        let origin = Vector3::new(a.x() - direction.x() * t, a.y() - direction.y() * t, 0.0);
+
+        let origin = transform_point(
+            &inverse_view,
+            Vector4::new(origin.x(), origin.y(), origin.z(), 1.0),
+        )?;
+        let direction = inverse_view.transform_vector(Vector4::new(
+            direction.x(),
+            direction.y(),
+            direction.z(),
+            0.0,
+        ));
+
+        Some(Ray::new(
+            origin,
+            Vector3::new(direction.x(), direction.y(), direction.z()),
+        ))
+    }
+
+    pub fn origin(&self) -> Vector3 {
+        self.origin
+    }
+
+    pub fn direction(&self) -> Vector3 {
+        self.direction
+    }
+
+    pub fn at(&self, distance: f32) -> Vector3 {
+        Vector3::new(
+            self.origin.x() + self.direction.x() * distance,
+            self.origin.y() + self.direction.y() * distance,
+            self.origin.z() + self.direction.z() * distance,
+        )
+    }
+
+    // The intersection functions return the distance along the ray to the nearest hit
+    pub fn intersect_box(&self, bounding_box: &BoundingBox) -> Option<f32> {
+        let origin = [self.origin.x(), self.origin.y(), self.origin.z()];
+        let direction = [self.direction.x(), self.direction.y(), self.direction.z()];
+        let min = bounding_box.min();
+        let max = bounding_box.max();
+        let min = [min.x(), min.y(), min.z()];
+        let max = [max.x(), max.y(), max.z()];
+
+        let mut near = 0.0f32;
+        let mut far = f32::INFINITY;
+        for i in 0..3 {
+            if direction[i] == 0.0 {
+                // Parallel to the slab, so the origin must be inside it
+                if origin[i] < min[i] || origin[i] > max[i] {
+                    return None;
+                }
+                continue;
+            }
+
+            let inv = 1.0 / direction[i];
+            let t0 = (min[i] - origin[i]) * inv;
+            let t1 = (max[i] - origin[i]) * inv;
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+
+            if near > far {
+                return None;
+            }
+        }
+
+        Some(near)
+    }
+
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<f32> {
+        let offset = self.origin - sphere.center();
+        let b = offset.dot(self.direction);
+        let c = offset.dot(offset) - sphere.radius() * sphere.radius();
+
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        if -b - root >= 0.0 {
+            Some(-b - root)
+        } else if -b + root >= 0.0 {
+            // The origin is inside the sphere
+            Some(0.0)
+        } else {
+            None
+        }
+    }
+
+    // Hits both sides of the triangle
+    pub fn intersect_triangle(&self, a: Vector3, b: Vector3, c: Vector3) -> Option<f32> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+
+        let p = self.direction.cross(edge2);
+        let determinant = edge1.dot(p);
+        if determinant.abs() < TRIANGLE_EPSILON {
+            return None;
+        }
+
+        let inv_det = 1.0 / determinant;
+        let s = self.origin - a;
+        let u = s.dot(p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = s.cross(edge1);
+        let v = self.direction.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(q) * inv_det;
+        if t >= 0.0 {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alexandria_common::Matrix;
+    use std::f32::consts::FRAC_PI_2;
+
+    const EPSILON: f32 = 1.0e-3;
+
+    fn assert_vector3_eq(a: Vector3, b: Vector3) {
+        assert!(
+            (a.x() - b.x()).abs() < EPSILON
+                && (a.y() - b.y()).abs() < EPSILON
+                && (a.z() - b.z()).abs() < EPSILON,
+            "({}, {}, {}) != ({}, {}, {})",
+            a.x(),
+            a.y(),
+            a.z(),
+            b.x(),
+            b.y(),
+            b.z()
+        );
+    }
+
+    fn camera() -> (LHRowMajorMatrix, LHRowMajorMatrix) {
+        let view = LHRowMajorMatrix::look_at(
+            Vector3::new(1.0, 2.0, -10.0),
+            Vector3::new(1.0, 2.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        let projection = LHRowMajorMatrix::perspective(FRAC_PI_2, 4.0 / 3.0, 0.5, 100.0);
+        (view, projection)
+    }
+
+    #[test]
+    fn project_unproject_round_trip() {
+        let (view, projection) = camera();
+        let top_left = Vector2::new(100.0, 50.0);
+        let size = Vector2::new(800.0, 600.0);
+
+        let points = [
+            Vector3::new(1.0, 2.0, 0.0),
+            Vector3::new(-3.0, 4.0, 5.0),
+            Vector3::new(6.0, -1.0, 40.0),
+        ];
+        for point in points {
+            let screen = project(point, top_left, size, &view, &projection).unwrap();
+            let unprojected = unproject(screen, top_left, size, &view, &projection).unwrap();
+            assert_vector3_eq(unprojected, point);
+        }
+
+        // The camera target lands in the middle of the viewport
+        let center = project(points[0], top_left, size, &view, &projection).unwrap();
+        assert!((center.x() - 500.0).abs() < EPSILON && (center.y() - 350.0).abs() < EPSILON);
+
+        // Behind the camera
+        assert!(project(
+            Vector3::new(1.0, 2.0, -20.0),
+            top_left,
+            size,
+            &view,
+            &projection
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn ray_from_cursor() {
+        let (view, projection) = camera();
+        let top_left = Vector2::new(0.0, 0.0);
+        let size = Vector2::new(800.0, 600.0);
+
+        // The center of the window is (0, 0) for Input
+        let screen = cursor_to_screen((0, 0), (800, 600));
+        assert!(screen.x() == 400.0 && screen.y() == 300.0);
+
+        let ray = Ray::from_screen(screen, top_left, size, &view, &projection).unwrap();
+        assert_vector3_eq(ray.origin(), Vector3::new(1.0, 2.0, -10.0));
+        assert_vector3_eq(ray.direction(), Vector3::new(0.0, 0.0, 1.0));
+
+        // A ray through any pixel passes through the unprojected point under it
+        let screen = cursor_to_screen((-150, 120), (800, 600));
+        let ray = Ray::from_screen(screen, top_left, size, &view, &projection).unwrap();
+        let point = unproject(
+            Vector3::new(screen.x(), screen.y(), 0.9),
+            top_left,
+            size,
+            &view,
+            &projection,
+        )
+        .unwrap();
+        let distance = (point - ray.origin()).dot(ray.direction());
+        assert_vector3_eq(ray.at(distance), point);
+    }
+
+    #[test]
+    fn ray_box() {
+        let bounding_box =
+            BoundingBox::new(Vector3::new(-1.0, -1.0, 4.0), Vector3::new(1.0, 1.0, 6.0));
+        let forward = |x, y| Ray::new(Vector3::new(x, y, 0.0), Vector3::new(0.0, 0.0, 1.0));
+
+        assert!((forward(0.0, 0.0).intersect_box(&bounding_box).unwrap() - 4.0).abs() < EPSILON);
+        assert!((forward(0.9, -0.9).intersect_box(&bounding_box).unwrap() - 4.0).abs() < EPSILON);
+        assert!(forward(2.0, 0.0).intersect_box(&bounding_box).is_none());
+
+        // Pointing away, and starting inside
+        let away = Ray::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(away.intersect_box(&bounding_box).is_none());
+        let inside = Ray::new(Vector3::new(0.0, 0.0, 5.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(inside.intersect_box(&bounding_box), Some(0.0));
+    }
+
+    #[test]
+    fn ray_sphere() {
+        let sphere = BoundingSphere::new(Vector3::new(0.0, 0.0, 10.0), 2.0);
+        let forward = |x, y| Ray::new(Vector3::new(x, y, 0.0), Vector3::new(0.0, 0.0, 1.0));
+
+        assert!((forward(0.0, 0.0).intersect_sphere(&sphere).unwrap() - 8.0).abs() < EPSILON);
+        assert!(forward(0.0, 2.5).intersect_sphere(&sphere).is_none());
+
+        let away = Ray::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(away.intersect_sphere(&sphere).is_none());
+        let inside = Ray::new(Vector3::new(0.0, 0.0, 10.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_eq!(inside.intersect_sphere(&sphere), Some(0.0));
+    }
+
+    #[test]
+    fn ray_triangle() {
+        let a = Vector3::new(-1.0, -1.0, 3.0);
+        let b = Vector3::new(1.0, -1.0, 3.0);
+        let c = Vector3::new(0.0, 1.0, 3.0);
+        let forward = |x, y| Ray::new(Vector3::new(x, y, 0.0), Vector3::new(0.0, 0.0, 1.0));
+
+        assert!((forward(0.0, 0.0).intersect_triangle(a, b, c).unwrap() - 3.0).abs() < EPSILON);
+        // Both windings hit
+        assert!(forward(0.0, 0.0).intersect_triangle(a, c, b).is_some());
+        assert!(forward(0.9, 0.9).intersect_triangle(a, b, c).is_none());
+
+        let away = Ray::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(away.intersect_triangle(a, b, c).is_none());
+        let parallel = Ray::new(Vector3::new(0.0, 0.0, 3.0), Vector3::new(1.0, 0.0, 0.0));
+        assert!(parallel.intersect_triangle(a, b, c).is_none());
+    }
+}
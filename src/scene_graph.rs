@@ -0,0 +1,378 @@
+use crate::{LHRowMajorMatrix, Transform};
+use alexandria_common::Matrix;
+use std::collections::HashMap;
+
+// A node is dirty whenever its world matrix is out of date. Marking a node dirty also marks
+// all of its descendants, so a dirty node never has a clean descendant.
+struct Node<T> {
+    local: Transform,
+    world: LHRowMajorMatrix,
+    dirty: bool,
+
+    parent: Option<usize>,
+    children: Vec<usize>,
+
+    data: T,
+}
+
+pub struct SceneGraph<T> {
+    nodes: HashMap<usize, Node<T>>,
+    roots: Vec<usize>,
+    new_node_key: usize,
+}
+
+// Visits nodes depth first, parents before their children and siblings in insertion order
+pub struct SceneGraphIter<'a, T> {
+    graph: &'a SceneGraph<T>,
+    stack: Vec<usize>,
+}
+
+impl<T> SceneGraph<T> {
+    pub fn new() -> Self {
+        SceneGraph {
+            nodes: HashMap::new(),
+            roots: Vec::new(),
+            new_node_key: 0,
+        }
+    }
+
+    pub fn add_node(&mut self, local: Transform, data: T) -> usize {
+        let key = self.insert(local, None, data);
+        self.roots.push(key);
+        key
+    }
+
+    pub fn add_child(&mut self, parent: usize, local: Transform, data: T) -> Option<usize> {
+        if !self.nodes.contains_key(&parent) {
+            return None;
+        }
+
+        let key = self.insert(local, Some(parent), data);
+        self.nodes.get_mut(&parent).unwrap().children.push(key);
+        Some(key)
+    }
+
+    // Removes the node along with all of its descendants
+    pub fn remove_node(&mut self, node: usize) {
+        let parent = match self.nodes.get(&node) {
+            Some(removed) => removed.parent,
+            None => return,
+        };
+        self.detach(node, parent);
+
+        let mut stack = vec![node];
+        while let Some(key) = stack.pop() {
+            if let Some(removed) = self.nodes.remove(&key) {
+                stack.extend(removed.children);
+            }
+        }
+    }
+
+    // Moves a node under a new parent, keeping its local transform. Fails if either node does
+    // not exist or if the new parent is the node itself or one of its descendants.
+    pub fn set_parent(&mut self, node: usize, parent: Option<usize>) -> bool {
+        let old_parent = match self.nodes.get(&node) {
+            Some(node) => node.parent,
+            None => return false,
+        };
+
+        if let Some(parent) = parent {
+            if !self.nodes.contains_key(&parent) || self.is_ancestor(node, parent) {
+                return false;
+            }
+        }
+
+        self.detach(node, old_parent);
+        match parent {
+            Some(parent) => self.nodes.get_mut(&parent).unwrap().children.push(node),
+            None => self.roots.push(node),
+        }
+        self.nodes.get_mut(&node).unwrap().parent = parent;
+
+        self.mark_dirty(node);
+        true
+    }
+
+    pub fn contains(&self, node: usize) -> bool {
+        self.nodes.contains_key(&node)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.nodes.get(&node).and_then(|node| node.parent)
+    }
+
+    pub fn children(&self, node: usize) -> &[usize] {
+        self.nodes
+            .get(&node)
+            .map(|node| node.children.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    pub fn local(&self, node: usize) -> Option<&Transform> {
+        self.nodes.get(&node).map(|node| &node.local)
+    }
+
+    pub fn local_mut(&mut self, node: usize) -> Option<&mut Transform> {
+        if !self.nodes.contains_key(&node) {
+            return None;
+        }
+
+        self.mark_dirty(node);
+        self.nodes.get_mut(&node).map(|node| &mut node.local)
+    }
+
+    pub fn set_local(&mut self, node: usize, local: Transform) {
+        if let Some(transform) = self.local_mut(node) {
+            *transform = local;
+        }
+    }
+
+    pub fn data(&self, node: usize) -> Option<&T> {
+        self.nodes.get(&node).map(|node| &node.data)
+    }
+
+    pub fn data_mut(&mut self, node: usize) -> Option<&mut T> {
+        self.nodes.get_mut(&node).map(|node| &mut node.data)
+    }
+
+    pub fn is_dirty(&self, node: usize) -> bool {
+        self.nodes
+            .get(&node)
+            .map(|node| node.dirty)
+            .unwrap_or(false)
+    }
+
+    // Recomputes the world matrix of the node and its ancestors if they are out of date
+    pub fn world_matrix(&mut self, node: usize) -> Option<LHRowMajorMatrix> {
+        if !self.nodes.contains_key(&node) {
+            return None;
+        }
+
+        Some(self.update_node(node))
+    }
+
+    // Brings every world matrix up to date
+    pub fn update_world_matrices(&mut self) {
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+        while let Some(key) = stack.pop() {
+            self.update_node(key);
+            stack.extend(self.nodes[&key].children.iter().rev());
+        }
+    }
+
+    // Yields the world matrices as of the last update, so call update_world_matrices first
+    pub fn iter(&self) -> SceneGraphIter<'_, T> {
+        let stack = self.roots.iter().rev().copied().collect();
+        SceneGraphIter { graph: self, stack }
+    }
+
+    fn insert(&mut self, local: Transform, parent: Option<usize>, data: T) -> usize {
+        let key = self.new_node_key;
+        self.new_node_key += 1;
+
+        self.nodes.insert(
+            key,
+            Node {
+                local,
+                world: LHRowMajorMatrix::identity(),
+                dirty: true,
+                parent,
+                children: Vec::new(),
+                data,
+            },
+        );
+
+        key
+    }
+
+    fn detach(&mut self, node: usize, parent: Option<usize>) {
+        match parent {
+            Some(parent) => {
+                if let Some(parent) = self.nodes.get_mut(&parent) {
+                    parent.children.retain(|child| *child != node);
+                }
+            }
+            None => self.roots.retain(|root| *root != node),
+        }
+    }
+
+    fn is_ancestor(&self, ancestor: usize, mut node: usize) -> bool {
+        loop {
+            if node == ancestor {
+                return true;
+            }
+
+            node = match self.nodes.get(&node).and_then(|node| node.parent) {
+                Some(parent) => parent,
+                None => return false,
+            };
+        }
+    }
+
+    fn mark_dirty(&mut self, node: usize) {
+        let mut stack = vec![node];
+        while let Some(key) = stack.pop() {
+            let node = self.nodes.get_mut(&key).unwrap();
+
+            // The descendants of a dirty node are already dirty
+            if node.dirty {
+                continue;
+            }
+
+            node.dirty = true;
+            stack.extend(node.children.iter().copied());
+        }
+    }
+
+    fn update_node(&mut self, node: usize) -> LHRowMajorMatrix {
+        let (dirty, parent) = {
+            let node = &self.nodes[&node];
+            (node.dirty, node.parent)
+        };
+
+        if !dirty {
+            return self.nodes[&node].world;
+        }
+
+        let parent_world = parent.map(|parent| self.update_node(parent));
+
+        let node = self.nodes.get_mut(&node).unwrap();
+        let local = node.local.to_matrix();
+        node.world = match parent_world {
+            Some(parent_world) => parent_world * local,
+            None => local,
+        };
+        node.dirty = false;
+        node.world
+    }
+}
+
+impl<T> Default for SceneGraph<T> {
+    fn default() -> Self {
+        SceneGraph::new()
+    }
+}
+
+impl<'a, T> Iterator for SceneGraphIter<'a, T> {
+    type Item = (usize, &'a LHRowMajorMatrix, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.stack.pop()?;
+        let node = &self.graph.nodes[&key];
+        self.stack.extend(node.children.iter().rev());
+        Some((key, &node.world, &node.data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alexandria_common::Vector3;
+
+    fn translated(x: f32, y: f32, z: f32) -> Transform {
+        let mut transform = Transform::identity();
+        transform.set_translation(Vector3::new(x, y, z));
+        transform
+    }
+
+    fn position(graph: &mut SceneGraph<()>, node: usize) -> [f32; 3] {
+        let world = graph.world_matrix(node).unwrap();
+        [world.get(0, 3), world.get(1, 3), world.get(2, 3)]
+    }
+
+    #[test]
+    fn parent_moves_child() {
+        let mut graph = SceneGraph::new();
+        let parent = graph.add_node(translated(1.0, 0.0, 0.0), ());
+        let child = graph
+            .add_child(parent, translated(0.0, 2.0, 0.0), ())
+            .unwrap();
+        assert_eq!(position(&mut graph, child), [1.0, 2.0, 0.0]);
+
+        graph.set_local(parent, translated(5.0, 0.0, 1.0));
+        assert!(graph.is_dirty(child));
+        assert_eq!(position(&mut graph, child), [5.0, 2.0, 1.0]);
+        assert!(!graph.is_dirty(child));
+    }
+
+    #[test]
+    fn reparenting() {
+        let mut graph = SceneGraph::new();
+        let a = graph.add_node(translated(1.0, 0.0, 0.0), ());
+        let b = graph.add_node(translated(0.0, 0.0, 3.0), ());
+        let child = graph.add_child(a, translated(0.0, 1.0, 0.0), ()).unwrap();
+        graph.update_world_matrices();
+
+        assert!(graph.set_parent(child, Some(b)));
+        assert!(graph.is_dirty(child));
+        assert_eq!(graph.parent(child), Some(b));
+        assert!(graph.children(a).is_empty());
+        assert_eq!(graph.children(b), &[child]);
+        assert_eq!(position(&mut graph, child), [0.0, 1.0, 3.0]);
+
+        assert!(graph.set_parent(child, None));
+        assert_eq!(graph.roots(), &[a, b, child]);
+        assert_eq!(position(&mut graph, child), [0.0, 1.0, 0.0]);
+
+        // A node cannot move under itself or its descendants
+        assert!(graph.set_parent(b, Some(a)));
+        assert!(!graph.set_parent(a, Some(b)));
+        assert!(!graph.set_parent(a, Some(a)));
+        assert!(!graph.set_parent(a, Some(100)));
+    }
+
+    #[test]
+    fn dirty_propagates_to_grandchildren() {
+        let mut graph = SceneGraph::new();
+        let root = graph.add_node(translated(1.0, 0.0, 0.0), ());
+        let child = graph
+            .add_child(root, translated(1.0, 0.0, 0.0), ())
+            .unwrap();
+        let grandchild = graph
+            .add_child(child, translated(1.0, 0.0, 0.0), ())
+            .unwrap();
+        let other = graph.add_node(Transform::identity(), ());
+        graph.update_world_matrices();
+        assert!(!graph.is_dirty(grandchild));
+
+        graph
+            .local_mut(root)
+            .unwrap()
+            .set_translation(Vector3::new(2.0, 0.0, 0.0));
+        assert!(graph.is_dirty(root) && graph.is_dirty(child) && graph.is_dirty(grandchild));
+        assert!(!graph.is_dirty(other));
+
+        graph.update_world_matrices();
+        assert!(!graph.is_dirty(grandchild));
+        assert_eq!(position(&mut graph, grandchild), [4.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn iter_visits_parents_first() {
+        let mut graph = SceneGraph::new();
+        let a = graph.add_node(translated(1.0, 0.0, 0.0), 'a');
+        let b = graph.add_child(a, translated(1.0, 0.0, 0.0), 'b').unwrap();
+        graph.add_child(b, translated(1.0, 0.0, 0.0), 'c').unwrap();
+        graph.add_child(a, translated(0.0, 1.0, 0.0), 'd').unwrap();
+        graph.add_node(Transform::identity(), 'e');
+        graph.update_world_matrices();
+
+        let order: String = graph.iter().map(|(_, _, data)| *data).collect();
+        assert_eq!(order, "abcde");
+
+        let worlds: Vec<f32> = graph.iter().map(|(_, world, _)| world.get(0, 3)).collect();
+        assert_eq!(worlds, [1.0, 2.0, 3.0, 1.0, 0.0]);
+    }
+}
@@ -1,12 +1,41 @@
+use crate::{
+    hlsl_source::defines_function,
+    input_layout::{convert_vertex_layout, VertexElement},
+    ShaderCache, ShaderCacheKey, ShaderConstantBufferDesc, ShaderStages, VertexLayout,
+};
 use alexandria_common::{Format, Input};
-use std::{cell::RefCell, ffi::CString, rc::Rc};
+use std::{
+    cell::RefCell,
+    ffi::CString,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
+};
 use win32::{DirectXError, ID3DBlob};
 
 pub struct Shader {
     vertex_shader: win32::ID3D11VertexShader,
     pixel_shader: win32::ID3D11PixelShader,
+    geometry_shader: Option<win32::ID3D11GeometryShader>,
+    hull_shader: Option<win32::ID3D11HullShader>,
+    domain_shader: Option<win32::ID3D11DomainShader>,
     input_layout: win32::ID3D11InputLayout,
+    vertex_stride: usize,
+    stages: ShaderStages,
+    constant_buffers: Vec<ShaderConstantBufferDesc>,
     device_context: Rc<RefCell<win32::ID3D11DeviceContext>>,
+
+    // Only set for shaders created from a file
+    file: Option<ShaderFile>,
+}
+
+// Everything needed to recompile a shader when its source file changes
+struct ShaderFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    vertex_layout: Option<Vec<VertexElement>>,
+    cache: Option<ShaderCache>,
+    device: Rc<win32::ID3D11Device>,
 }
 
 pub struct ShaderCreationError {
@@ -14,69 +43,406 @@ pub struct ShaderCreationError {
     blob: Option<ID3DBlob>,
 }
 
+// A shader needs vertex and pixel stages, and a hull stage is only valid with a domain stage
+#[derive(Debug)]
+pub struct ShaderStagesError(ShaderStages);
+
+// Every stage a shader may include, with its entry point and target. The vertex and pixel
+// stages come first.
+const STAGES: &[(ShaderStages, &str, &str)] = &[
+    (ShaderStages::VERTEX, "vertex_main", "vs_5_0"),
+    (ShaderStages::PIXEL, "fragment_main", "ps_5_0"),
+    (ShaderStages::GEOMETRY, "geometry_main", "gs_5_0"),
+    (ShaderStages::HULL, "hull_main", "hs_5_0"),
+    (ShaderStages::DOMAIN, "domain_main", "ds_5_0"),
+];
+
+// Passed to d3d_compile, and folded into cache keys so changing them invalidates the cache
+const COMPILE_FLAGS: &[win32::D3DCompileFlag] = &[win32::D3DCompileFlag::EnableStrictness];
+
+// The compiler win32 links against, part of cache keys for the same reason
+const COMPILER_VERSION: &str = "d3dcompiler_47";
+
+// The acsl package Cargo.lock resolved, baked in by build.rs
+const ACSL_VERSION: &str = env!("ACSL_VERSION");
+
+pub(crate) fn compile(
+    shader_code: &CString,
+    entry_point: &str,
+    target: &str,
+) -> Result<ID3DBlob, ShaderCreationError> {
+    let (blob, errors) = win32::d3d_compile(
+        shader_code,
+        None,
+        &[],
+        Some(&CString::new(entry_point).unwrap()),
+        &CString::new(target).unwrap(),
+        COMPILE_FLAGS,
+        &[],
+    );
+
+    blob.map_err(|error| ShaderCreationError::new(error, errors))
+}
+
+// None when the file is missing, such as while an editor replaces it
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn validate_stages(stages: ShaderStages) -> Result<(), ShaderStagesError> {
+    let required = ShaderStages::VERTEX | ShaderStages::PIXEL;
+    let tessellation = ShaderStages::HULL | ShaderStages::DOMAIN;
+    if !stages.contains(required)
+        || stages.contains(ShaderStages::COMPUTE)
+        || (!(stages & tessellation).is_empty() && !stages.contains(tessellation))
+    {
+        return Err(ShaderStagesError(stages));
+    }
+
+    Ok(())
+}
+
+// Keyed by the ACSL so a hit skips acsl entirely, with the acsl version standing in for its
+// output so an upgrade misses instead of loading stale bytecode
+fn cache_key(code: &str, stage: usize) -> ShaderCacheKey {
+    let (_, entry_point, target) = STAGES[stage];
+    let flags = COMPILE_FLAGS
+        .iter()
+        .fold(0, |flags, flag| flags | *flag as u32);
+    ShaderCacheKey::new(
+        code,
+        entry_point,
+        target,
+        flags,
+        COMPILER_VERSION,
+        ACSL_VERSION,
+    )
+}
+
+// Loads the bytecode of the given stages, or of every stage the source defines when stages is
+// None. Returns None unless every stage is cached.
+fn load_cached(
+    cache: &ShaderCache,
+    code: &str,
+    stages: Option<ShaderStages>,
+) -> Result<Option<Vec<Option<ID3DBlob>>>, DirectXError> {
+    let mut blobs = Vec::with_capacity(STAGES.len());
+    for (i, (stage, _, _)) in STAGES.iter().enumerate() {
+        if stages.is_some_and(|stages| !stages.contains(*stage)) {
+            blobs.push(None);
+            continue;
+        }
+
+        let bytecode = match cache.load(cache_key(code, i)) {
+            Some(bytecode) => bytecode,
+            None => return Ok(None),
+        };
+
+        // An empty entry records that the source has no such stage, which only answers the
+        // lookup when the stages are detected from the source
+        if bytecode.is_empty() {
+            match stages {
+                Some(_) => return Ok(None),
+                None => blobs.push(None),
+            }
+        } else {
+            blobs.push(Some(win32::d3d_create_blob(&bytecode)?));
+        }
+    }
+
+    Ok(Some(blobs))
+}
+
+// Compiles ACSL down to the bytecode of each stage, indexed like STAGES. Without explicit
+// stages, includes the geometry, hull and domain stages whose entry points the source defines.
+// Cache misses and write failures fall back to compiling.
+pub(crate) fn compile_stages<S: AsRef<str>>(
+    code: S,
+    stages: Option<ShaderStages>,
+    cache: Option<&ShaderCache>,
+) -> Result<Vec<Option<ID3DBlob>>, Box<dyn std::error::Error>> {
+    let code = code.as_ref();
+
+    if let Some(cache) = cache {
+        if let Some(blobs) = load_cached(cache, code, stages)? {
+            return Ok(blobs);
+        }
+    }
+
+    let hlsl_code = acsl::compile_hlsl(code)?;
+    let shader_code = CString::new(hlsl_code.as_str()).unwrap();
+
+    let mut blobs = Vec::with_capacity(STAGES.len());
+    for (stage, entry_point, target) in STAGES {
+        let included = match stages {
+            Some(stages) => stages.contains(*stage),
+            None => {
+                *stage == ShaderStages::VERTEX
+                    || *stage == ShaderStages::PIXEL
+                    || defines_function(&hlsl_code, entry_point)
+            }
+        };
+
+        blobs.push(match included {
+            true => Some(compile(&shader_code, entry_point, target)?),
+            false => None,
+        });
+    }
+
+    if let Some(cache) = cache {
+        for (i, blob) in blobs.iter().enumerate() {
+            let result = match blob {
+                Some(blob) => cache.store(cache_key(code, i), blob.as_slice()),
+                None if stages.is_none() => cache.store(cache_key(code, i), &[]),
+                None => continue,
+            };
+
+            if result.is_err() {
+                break;
+            }
+        }
+    }
+
+    Ok(blobs)
+}
+
 impl alexandria_common::Shader for Shader {
     type Window<I: Input> = Box<crate::Window<I>>;
 
+    // Includes the geometry, hull and domain stages whose entry points the source defines.
+    // The vertex layout is checked against the inputs of the vertex shader, see
+    // Shader::new_with_layout to generate it from them instead.
     fn new<S: AsRef<str>, I: Input>(
         code: S,
         vertex_layout: &[(&str, Format)],
         window: &mut Self::Window<I>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let hlsl_code = acsl::compile_hlsl(code)?;
-
-        let device = window.device();
-
-        let shader_code = CString::new(hlsl_code).unwrap();
-        let (vertex_shader_blob, errors) = win32::d3d_compile(
-            &shader_code,
-            None,
-            &[],
-            Some(&CString::new("vertex_main").unwrap()),
-            &CString::new("vs_5_0").unwrap(),
-            &[win32::D3DCompileFlag::EnableStrictness],
-            &[],
-        );
-
-        let (vertex_shader, vertex_shader_blob) = match vertex_shader_blob {
-            Ok(blob) => (device.create_vertex_shader(&blob)?, blob),
-            Err(error) => {
-                return Err(Box::new(ShaderCreationError {
-                    error,
-                    blob: errors,
-                }))
-            }
+        Shader::new_with_layout(code, VertexLayout::Explicit(vertex_layout), window)
+    }
+
+    fn set_active(&mut self) {
+        let mut device_context = self.device_context.borrow_mut();
+        device_context.ia_set_input_layout(Some(&mut self.input_layout));
+        device_context.vs_set_shader(Some(&mut self.vertex_shader));
+        device_context.hs_set_shader(self.hull_shader.as_mut());
+        device_context.ds_set_shader(self.domain_shader.as_mut());
+        device_context.gs_set_shader(self.geometry_shader.as_mut());
+        device_context.ps_set_shader(Some(&mut self.pixel_shader));
+    }
+
+    fn clear_active(&mut self) {
+        let mut device_context = self.device_context.borrow_mut();
+        device_context.ia_set_input_layout(None);
+        device_context.vs_set_shader(None);
+        device_context.hs_set_shader(None);
+        device_context.ds_set_shader(None);
+        device_context.gs_set_shader(None);
+        device_context.ps_set_shader(None);
+    }
+}
+
+impl Shader {
+    // Compiles like Shader::new, but can also generate the vertex layout from the inputs of the
+    // vertex shader
+    pub fn new_with_layout<S: AsRef<str>, I: Input>(
+        code: S,
+        vertex_layout: VertexLayout,
+        window: &mut crate::Window<I>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let blobs = compile_stages(code, None, window.shader_cache())?;
+        Shader::from_blobs(
+            &blobs,
+            convert_vertex_layout(vertex_layout).as_deref(),
+            window.device(),
+            window.device_context(),
+        )
+    }
+
+    // Compiles exactly the given stages, which must include the vertex and pixel stages. Hull
+    // and domain stages come as a pair, and draw with a patch list topology.
+    pub fn new_with_stages<S: AsRef<str>, I: Input>(
+        code: S,
+        vertex_layout: VertexLayout,
+        stages: ShaderStages,
+        window: &mut crate::Window<I>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        validate_stages(stages)?;
+        let blobs = compile_stages(code, Some(stages), window.shader_cache())?;
+        Shader::from_blobs(
+            &blobs,
+            convert_vertex_layout(vertex_layout).as_deref(),
+            window.device(),
+            window.device_context(),
+        )
+    }
+
+    // Compiles the ACSL source at path like Shader::new, remembering the path so the shader
+    // can be reloaded when the file changes
+    pub fn from_file<P: AsRef<Path>, I: Input>(
+        path: P,
+        vertex_layout: VertexLayout,
+        window: &mut crate::Window<I>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified_time(&path);
+        let code = std::fs::read_to_string(&path)?;
+
+        let vertex_layout = convert_vertex_layout(vertex_layout);
+        let blobs = compile_stages(code, None, window.shader_cache())?;
+        let mut shader = Shader::from_blobs(
+            &blobs,
+            vertex_layout.as_deref(),
+            window.device(),
+            window.device_context(),
+        )?;
+
+        shader.file = Some(ShaderFile {
+            path,
+            modified,
+            vertex_layout,
+            cache: window.shader_cache().cloned(),
+            device: window.device().clone(),
+        });
+
+        Ok(shader)
+    }
+
+    // The source file of a shader created with from_file
+    pub fn path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|file| file.path.as_path())
+    }
+
+    // Reloads the shader if its source file was modified since it was last loaded, returning
+    // whether it was reloaded. This polls the modification time of the file rather than
+    // watching it, so call it every frame, or as often as changes should be picked up. A failed
+    // reload is only reported once per modification, and compiler output arrives as a
+    // ShaderCreationError inside the box.
+    pub fn reload_if_changed(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => return Ok(false),
         };
 
-        let (pixel_shader_blob, errors) = win32::d3d_compile(
-            &shader_code,
-            None,
-            &[],
-            Some(&CString::new("fragment_main").unwrap()),
-            &CString::new("ps_5_0").unwrap(),
-            &[win32::D3DCompileFlag::EnableStrictness],
-            &[],
-        );
-
-        let pixel_shader = match pixel_shader_blob {
-            Ok(blob) => device.create_pixel_shader(&blob)?,
-            Err(error) => {
-                return Err(Box::new(ShaderCreationError {
-                    error,
-                    blob: errors,
-                }))
+        let modified = modified_time(&file.path);
+        if modified.is_none() || modified == file.modified {
+            return Ok(false);
+        }
+        file.modified = modified;
+
+        self.reload().map(|_| true)
+    }
+
+    // Recompiles the shader from its source file and replaces its stages and input layout in
+    // place. On failure the shader keeps working with the previous ones. After a successful
+    // reload the shader must be set active again for the new stages to be used.
+    pub fn reload(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+
+        let result = Shader::compile_file(&file, &self.device_context).map(|shader| *self = shader);
+
+        self.file = Some(file);
+        result
+    }
+
+    // The size in bytes of one vertex as the input layout reads it, with every element packed
+    // one after another. The vertex type of meshes drawn with the shader must be this size,
+    // which is worth checking when the layout is reflected.
+    pub fn vertex_stride(&self) -> usize {
+        self.vertex_stride
+    }
+
+    // The stages the shader has an entry point for
+    pub fn stages(&self) -> ShaderStages {
+        self.stages
+    }
+
+    // The cbuffers declared by any stage of the shader
+    pub fn constant_buffers(&self) -> &[ShaderConstantBufferDesc] {
+        &self.constant_buffers
+    }
+
+    pub fn constant_buffer(&self, name: &str) -> Option<&ShaderConstantBufferDesc> {
+        self.constant_buffers
+            .iter()
+            .find(|constant_buffer| constant_buffer.name() == name)
+    }
+
+    fn compile_file(
+        file: &ShaderFile,
+        device_context: &Rc<RefCell<win32::ID3D11DeviceContext>>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let code = std::fs::read_to_string(&file.path)?;
+        let blobs = compile_stages(code, None, file.cache.as_ref())?;
+        Shader::from_blobs(
+            &blobs,
+            file.vertex_layout.as_deref(),
+            &file.device,
+            device_context,
+        )
+    }
+
+    fn from_blobs(
+        blobs: &[Option<ID3DBlob>],
+        vertex_layout: Option<&[VertexElement]>,
+        device: &win32::ID3D11Device,
+        device_context: &Rc<RefCell<win32::ID3D11DeviceContext>>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut stages = ShaderStages::NONE;
+        for ((stage, _, _), blob) in STAGES.iter().zip(blobs) {
+            if blob.is_some() {
+                stages |= *stage;
             }
+        }
+        validate_stages(stages)?;
+
+        let mut constant_buffers = Vec::new();
+        for ((stage, _, _), blob) in STAGES.iter().zip(blobs) {
+            if let Some(blob) = blob {
+                crate::shader_reflection::reflect_constant_buffers(
+                    blob,
+                    *stage,
+                    &mut constant_buffers,
+                )?;
+            }
+        }
+
+        let vertex_shader_blob = blobs[0].as_ref().unwrap();
+        let vertex_shader = device.create_vertex_shader(vertex_shader_blob)?;
+        let pixel_shader = device.create_pixel_shader(blobs[1].as_ref().unwrap())?;
+
+        let geometry_shader = match &blobs[2] {
+            Some(blob) => Some(device.create_geometry_shader(blob)?),
+            None => None,
+        };
+        let hull_shader = match &blobs[3] {
+            Some(blob) => Some(device.create_hull_shader(blob)?),
+            None => None,
+        };
+        let domain_shader = match &blobs[4] {
+            Some(blob) => Some(device.create_domain_shader(blob)?),
+            None => None,
         };
 
-        let mut input_layout_desc = Vec::with_capacity(vertex_layout.len());
-        let mut names = Vec::with_capacity(vertex_layout.len());
-        for (name, format) in vertex_layout {
-            let i = names.len();
-            names.push(CString::new(*name).unwrap());
+        // Checked up front, as create_input_layout does not say which element is wrong
+        let signature = crate::shader_reflection::reflect_input_signature(vertex_shader_blob)?;
+        let vertex_layout = crate::input_layout::resolve_input_layout(vertex_layout, &signature)?;
 
+        let names: Vec<_> = vertex_layout
+            .iter()
+            .map(|element| CString::new(element.semantic_name()).unwrap())
+            .collect();
+
+        let mut input_layout_desc = Vec::with_capacity(vertex_layout.len());
+        for (element, name) in vertex_layout.iter().zip(&names) {
             input_layout_desc.push(win32::D3D11InputElementDesc::new(
-                &names[i],
-                0,
-                crate::alexandria_to_dxgi(format),
+                name,
+                element.semantic_index() as u32,
+                element.format(),
                 0,
                 None,
                 win32::D3D11InputClassification::PerVertexData,
@@ -85,29 +451,23 @@ impl alexandria_common::Shader for Shader {
         }
 
         let input_layout =
-            device.create_input_layout(input_layout_desc.as_slice(), &vertex_shader_blob)?;
+            device.create_input_layout(input_layout_desc.as_slice(), vertex_shader_blob)?;
+        let vertex_stride = vertex_layout.iter().map(VertexElement::size).sum();
 
         Ok(Shader {
             vertex_shader,
             pixel_shader,
+            geometry_shader,
+            hull_shader,
+            domain_shader,
             input_layout,
-            device_context: window.device_context().clone(),
+            vertex_stride,
+            stages,
+            constant_buffers,
+            device_context: device_context.clone(),
+            file: None,
         })
     }
-
-    fn set_active(&mut self) {
-        let mut device_context = self.device_context.borrow_mut();
-        device_context.ia_set_input_layout(Some(&mut self.input_layout));
-        device_context.vs_set_shader(Some(&mut self.vertex_shader));
-        device_context.ps_set_shader(Some(&mut self.pixel_shader));
-    }
-
-    fn clear_active(&mut self) {
-        let mut device_context = self.device_context.borrow_mut();
-        device_context.ia_set_input_layout(None);
-        device_context.vs_set_shader(None);
-        device_context.ps_set_shader(None);
-    }
 }
 
 impl ShaderCreationError {
@@ -142,3 +502,15 @@ impl From<win32::DirectXError> for ShaderCreationError {
         ShaderCreationError { error, blob: None }
     }
 }
+
+impl std::error::Error for ShaderStagesError {}
+
+impl std::fmt::Display for ShaderStagesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid shader stages {:?}, expected vertex and pixel stages with hull and domain as a pair",
+            self.0
+        )
+    }
+}
@@ -0,0 +1,270 @@
+use std::path::{Path, PathBuf};
+
+// Bump whenever the layout of a cache file or the way keys are built changes
+const CACHE_FORMAT_VERSION: u32 = 3;
+
+const CACHE_MAGIC: [u8; 4] = *b"ADXC";
+
+// magic, format version, key, bytecode length and bytecode checksum
+const CACHE_HEADER_SIZE: usize = 4 + 4 + 8 + 8 + 8;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// Identifies the bytecode of one entry point compiled from one ACSL source by one version of
+// acsl and one compiler with one set of flags. Built from a hash which is stable across runs and Rust versions, so
+// it can name files on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShaderCacheKey(u64);
+
+// Stores compiled shader bytecode in a directory, one file per key. A file which is missing,
+// corrupt or written by another version of this crate is a miss.
+#[derive(Debug, Clone)]
+pub struct ShaderCache {
+    directory: PathBuf,
+}
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+// Every part is prefixed with its length, so moving bytes between parts changes the hash
+fn hash_part(hash: u64, part: &[u8]) -> u64 {
+    fnv1a(fnv1a(hash, &(part.len() as u64).to_le_bytes()), part)
+}
+
+impl ShaderCacheKey {
+    pub fn new(
+        code: &str,
+        entry_point: &str,
+        target: &str,
+        flags: u32,
+        compiler_version: &str,
+        acsl_version: &str,
+    ) -> Self {
+        let mut hash = FNV_OFFSET_BASIS;
+        hash = hash_part(hash, env!("CARGO_PKG_VERSION").as_bytes());
+        hash = hash_part(hash, compiler_version.as_bytes());
+        hash = hash_part(hash, acsl_version.as_bytes());
+        hash = hash_part(hash, code.as_bytes());
+        hash = hash_part(hash, entry_point.as_bytes());
+        hash = hash_part(hash, target.as_bytes());
+        hash = hash_part(hash, &flags.to_le_bytes());
+        ShaderCacheKey(hash)
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{:016x}.cso", self.0)
+    }
+
+    // Lays out a cache file as a header followed by the bytecode, with every integer little
+    // endian. An empty bytecode records that the source has no such entry point.
+    pub fn encode(&self, bytecode: &[u8]) -> Vec<u8> {
+        let mut file = Vec::with_capacity(CACHE_HEADER_SIZE + bytecode.len());
+        file.extend_from_slice(&CACHE_MAGIC);
+        file.extend_from_slice(&CACHE_FORMAT_VERSION.to_le_bytes());
+        file.extend_from_slice(&self.0.to_le_bytes());
+        file.extend_from_slice(&(bytecode.len() as u64).to_le_bytes());
+        file.extend_from_slice(&fnv1a(FNV_OFFSET_BASIS, bytecode).to_le_bytes());
+        file.extend_from_slice(bytecode);
+        file
+    }
+
+    // Returns the bytecode of a cache file, or None if it was written for another key or
+    // version or has been corrupted
+    pub fn decode<'a>(&self, file: &'a [u8]) -> Option<&'a [u8]> {
+        if file.len() < CACHE_HEADER_SIZE || file[0..4] != CACHE_MAGIC {
+            return None;
+        }
+
+        let read_u64 = |offset: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&file[offset..offset + 8]);
+            u64::from_le_bytes(bytes)
+        };
+
+        let version = u32::from_le_bytes([file[4], file[5], file[6], file[7]]);
+        let key = read_u64(8);
+        let length = read_u64(16);
+        let checksum = read_u64(24);
+
+        let bytecode = &file[CACHE_HEADER_SIZE..];
+        if version != CACHE_FORMAT_VERSION
+            || key != self.0
+            || length != bytecode.len() as u64
+            || checksum != fnv1a(FNV_OFFSET_BASIS, bytecode)
+        {
+            return None;
+        }
+
+        Some(bytecode)
+    }
+}
+
+impl ShaderCache {
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Self {
+        ShaderCache {
+            directory: directory.into(),
+        }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn load(&self, key: ShaderCacheKey) -> Option<Vec<u8>> {
+        let file = std::fs::read(self.directory.join(key.file_name())).ok()?;
+        key.decode(&file).map(|bytecode| bytecode.to_vec())
+    }
+
+    // Writes to a temporary file first, so a crash never leaves a partial file behind
+    pub fn store(&self, key: ShaderCacheKey, bytecode: &[u8]) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+
+        let path = self.directory.join(key.file_name());
+        let temporary_path = path.with_extension("tmp");
+        std::fs::write(&temporary_path, key.encode(bytecode))?;
+        std::fs::rename(temporary_path, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> ShaderCacheKey {
+        ShaderCacheKey::new(
+            "float4 vertex_main() {}",
+            "vertex_main",
+            "vs_5_0",
+            1 << 11,
+            "47",
+            "0.1.0",
+        )
+    }
+
+    #[test]
+    fn keys_depend_on_every_input() {
+        let key = key();
+        assert_eq!(key, self::key());
+        for other in [
+            ShaderCacheKey::new(
+                "float4 vertex_main() { }",
+                "vertex_main",
+                "vs_5_0",
+                1 << 11,
+                "47",
+                "0.1.0",
+            ),
+            ShaderCacheKey::new(
+                "float4 vertex_main() {}",
+                "fragment_main",
+                "vs_5_0",
+                1 << 11,
+                "47",
+                "0.1.0",
+            ),
+            ShaderCacheKey::new(
+                "float4 vertex_main() {}",
+                "vertex_main",
+                "vs_4_0",
+                1 << 11,
+                "47",
+                "0.1.0",
+            ),
+            ShaderCacheKey::new(
+                "float4 vertex_main() {}",
+                "vertex_main",
+                "vs_5_0",
+                0,
+                "47",
+                "0.1.0",
+            ),
+            ShaderCacheKey::new(
+                "float4 vertex_main() {}",
+                "vertex_main",
+                "vs_5_0",
+                1 << 11,
+                "47",
+                "0.2.0",
+            ),
+            ShaderCacheKey::new(
+                "float4 vertex_main() {}",
+                "vertex_main",
+                "vs_5_0",
+                1 << 11,
+                "46",
+                "0.1.0",
+            ),
+            ShaderCacheKey::new(
+                "float4 vertex_main(",
+                ") {}vertex_main",
+                "vs_5_0",
+                1 << 11,
+                "47",
+                "0.1.0",
+            ),
+        ] {
+            assert_ne!(key, other);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let key = key();
+        let bytecode = [1, 2, 3, 4, 5];
+        assert_eq!(key.decode(&key.encode(&bytecode)), Some(&bytecode[..]));
+        assert_eq!(key.decode(&key.encode(&[])), Some(&[][..]));
+    }
+
+    #[test]
+    fn corrupted_files_miss() {
+        let key = key();
+        let file = key.encode(&[1, 2, 3, 4, 5]);
+
+        // Every byte of the header and bytecode is checked
+        for i in 0..file.len() {
+            let mut corrupted = file.clone();
+            corrupted[i] ^= 0x80;
+            assert_eq!(key.decode(&corrupted), None, "byte {}", i);
+        }
+
+        assert_eq!(key.decode(&file[..file.len() - 1]), None);
+        assert_eq!(key.decode(&file[..CACHE_HEADER_SIZE - 1]), None);
+        assert_eq!(key.decode(&[]), None);
+    }
+
+    #[test]
+    fn other_versions_and_keys_miss() {
+        let key = key();
+        let mut file = key.encode(&[1, 2, 3, 4, 5]);
+        file[4..8].copy_from_slice(&(CACHE_FORMAT_VERSION - 1).to_le_bytes());
+        assert_eq!(key.decode(&file), None);
+
+        let other = ShaderCacheKey::new("", "vertex_main", "vs_5_0", 1 << 11, "47", "0.1.0");
+        assert_eq!(other.decode(&key.encode(&[1, 2, 3, 4, 5])), None);
+    }
+
+    #[test]
+    fn store_and_load() {
+        let directory = std::env::temp_dir().join(format!("shader_cache_{}", std::process::id()));
+        let cache = ShaderCache::new(&directory);
+
+        let key = key();
+        assert_eq!(cache.load(key), None);
+        cache.store(key, &[1, 2, 3]).unwrap();
+        assert_eq!(cache.load(key), Some(vec![1, 2, 3]));
+        cache.store(key, &[4, 5]).unwrap();
+        assert_eq!(cache.load(key), Some(vec![4, 5]));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
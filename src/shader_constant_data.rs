@@ -0,0 +1,242 @@
+use crate::{
+    ShaderConstantBufferDesc, ShaderConstantError, ShaderScalarType, ShaderVariableClass,
+    ShaderVariableType,
+};
+
+// The contents of a constant buffer laid out from a shader's reflection. It places values in
+// the registers HLSL expects them in, so it is kept apart from the device.
+pub(crate) struct ShaderConstantData {
+    desc: ShaderConstantBufferDesc,
+    data: Vec<u8>,
+}
+
+impl ShaderConstantData {
+    pub(crate) fn new(desc: &ShaderConstantBufferDesc) -> Self {
+        ShaderConstantData {
+            desc: desc.clone(),
+            data: vec![0; desc.size()],
+        }
+    }
+
+    pub(crate) fn desc(&self) -> &ShaderConstantBufferDesc {
+        &self.desc
+    }
+
+    pub(crate) fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    // Writes a value given as rows * columns elements in memory order to the named variable
+    pub(crate) fn write(
+        &mut self,
+        name: &str,
+        scalar: ShaderScalarType,
+        rows: usize,
+        columns: usize,
+        values: &[u32],
+    ) -> Result<(), ShaderConstantError> {
+        let variable = self
+            .desc
+            .variable(name)
+            .ok_or_else(|| ShaderConstantError::UnknownVariable(name.to_string()))?;
+
+        let expected = ShaderVariableType::new(scalar, rows, columns);
+        if !variable.variable_type().matches(&expected) {
+            return Err(ShaderConstantError::TypeMismatch {
+                name: name.to_string(),
+                expected,
+                found: *variable.variable_type(),
+            });
+        }
+
+        // A matrix row or column starts a new register, while the last one is left unpadded
+        let (count, length) = match variable.variable_type().class() {
+            ShaderVariableClass::MatrixRows => (rows, columns),
+            ShaderVariableClass::MatrixColumns => (columns, rows),
+            _ => (1, values.len()),
+        };
+
+        let offset = variable.offset();
+        for i in 0..count {
+            for j in 0..length {
+                let start = offset + i * 16 + j * 4;
+                self.data[start..start + 4].copy_from_slice(&values[i * length + j].to_le_bytes());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ShaderStages, ShaderVariable};
+
+    const MATRIX: [u32; 3 * 3] = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+    // A float3x3 followed by a float HLSL packs into the end of its last register
+    fn constants(matrix_type: ShaderVariableType) -> ShaderConstantData {
+        ShaderConstantData::new(&ShaderConstantBufferDesc::new(
+            "Constants",
+            3 * 16,
+            0,
+            ShaderStages::VERTEX,
+            vec![
+                ShaderVariable::new("normal", 0, 2 * 16 + 3 * 4, matrix_type),
+                ShaderVariable::new(
+                    "scale",
+                    2 * 16 + 3 * 4,
+                    4,
+                    ShaderVariableType::new(ShaderScalarType::Float, 1, 1),
+                ),
+            ],
+        ))
+    }
+
+    fn read(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    }
+
+    fn registers(data: &[u8]) -> Vec<[u32; 4]> {
+        data.chunks_exact(16)
+            .map(|register| [0, 4, 8, 12].map(|offset| read(register, offset)))
+            .collect()
+    }
+
+    #[test]
+    fn row_major_rows_start_new_registers() {
+        let mut constants = constants(ShaderVariableType::new(ShaderScalarType::Float, 3, 3));
+        constants
+            .write("normal", ShaderScalarType::Float, 3, 3, &MATRIX)
+            .unwrap();
+
+        assert_eq!(
+            registers(constants.data()),
+            [[1, 2, 3, 0], [4, 5, 6, 0], [7, 8, 9, 0]]
+        );
+    }
+
+    // Elements are written in memory order whatever the packing, like ConstantBuffer does
+    #[test]
+    fn column_major_columns_start_new_registers() {
+        let mut constants = constants(ShaderVariableType::column_major(
+            ShaderScalarType::Float,
+            3,
+            3,
+        ));
+        constants
+            .write("normal", ShaderScalarType::Float, 3, 3, &MATRIX)
+            .unwrap();
+
+        assert_eq!(
+            registers(constants.data()),
+            [[1, 2, 3, 0], [4, 5, 6, 0], [7, 8, 9, 0]]
+        );
+    }
+
+    #[test]
+    fn registers_hold_as_many_elements_as_the_packing_gives_them() {
+        let desc = |variable_type| {
+            ShaderConstantBufferDesc::new(
+                "Constants",
+                3 * 16,
+                0,
+                ShaderStages::VERTEX,
+                vec![ShaderVariable::new(
+                    "matrix",
+                    0,
+                    2 * 16 + 2 * 4,
+                    variable_type,
+                )],
+            )
+        };
+
+        let mut rows = ShaderConstantData::new(&desc(ShaderVariableType::new(
+            ShaderScalarType::Float,
+            2,
+            3,
+        )));
+        rows.write("matrix", ShaderScalarType::Float, 2, 3, &[1, 2, 3, 4, 5, 6])
+            .unwrap();
+        assert_eq!(
+            registers(rows.data()),
+            [[1, 2, 3, 0], [4, 5, 6, 0], [0, 0, 0, 0]]
+        );
+
+        let mut columns = ShaderConstantData::new(&desc(ShaderVariableType::column_major(
+            ShaderScalarType::Float,
+            2,
+            3,
+        )));
+        columns
+            .write("matrix", ShaderScalarType::Float, 2, 3, &[1, 2, 3, 4, 5, 6])
+            .unwrap();
+        assert_eq!(
+            registers(columns.data()),
+            [[1, 2, 0, 0], [3, 4, 0, 0], [5, 6, 0, 0]]
+        );
+    }
+
+    #[test]
+    fn last_row_is_not_padded() {
+        let mut constants = constants(ShaderVariableType::new(ShaderScalarType::Float, 3, 3));
+        constants
+            .write("scale", ShaderScalarType::Float, 1, 1, &[10])
+            .unwrap();
+        constants
+            .write("normal", ShaderScalarType::Float, 3, 3, &MATRIX)
+            .unwrap();
+
+        assert_eq!(
+            registers(constants.data()),
+            [[1, 2, 3, 0], [4, 5, 6, 0], [7, 8, 9, 10]]
+        );
+    }
+
+    #[test]
+    fn unknown_variables_are_rejected() {
+        let mut constants = constants(ShaderVariableType::new(ShaderScalarType::Float, 3, 3));
+
+        assert!(matches!(
+            constants.write("world", ShaderScalarType::Float, 1, 1, &[10]),
+            Err(ShaderConstantError::UnknownVariable(name)) if name == "world"
+        ));
+        assert!(constants.data().iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn mismatched_types_are_rejected() {
+        let mut constants = constants(ShaderVariableType::new(ShaderScalarType::Float, 3, 3));
+
+        for (scalar, rows, columns) in [
+            (ShaderScalarType::Float, 4, 4),
+            (ShaderScalarType::Int, 3, 3),
+            (ShaderScalarType::Float, 1, 3),
+        ] {
+            let values = vec![1; rows * columns];
+            match constants.write("normal", scalar, rows, columns, &values) {
+                Err(ShaderConstantError::TypeMismatch {
+                    name,
+                    expected,
+                    found,
+                }) => {
+                    assert_eq!(name, "normal");
+                    assert_eq!(expected, ShaderVariableType::new(scalar, rows, columns));
+                    assert_eq!(
+                        found,
+                        ShaderVariableType::new(ShaderScalarType::Float, 3, 3)
+                    );
+                }
+                result => panic!("expected a type mismatch, found {:?}", result),
+            }
+        }
+
+        assert!(constants.data().iter().all(|byte| *byte == 0));
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    shader_constant_data::ShaderConstantData, LHRowMajorMatrix, ShaderConstantBufferDesc,
+    ShaderScalarType, ShaderStages, ShaderVariableType,
+};
+use alexandria_common::{Input, Vector2, Vector3, Vector4};
+use std::{cell::RefCell, rc::Rc};
+
+// A constant buffer laid out from a shader's reflection, so members are written by name
+// instead of through a Rust struct mirroring the cbuffer
+pub struct ShaderConstants {
+    data: ShaderConstantData,
+    dirty: bool,
+
+    constant_buffer: win32::ID3D11Buffer,
+    slot: usize,
+    stages: ShaderStages,
+    device_context: Rc<RefCell<win32::ID3D11DeviceContext>>,
+}
+
+#[derive(Debug)]
+pub enum ShaderConstantError {
+    UnknownVariable(String),
+    TypeMismatch {
+        name: String,
+        expected: ShaderVariableType,
+        found: ShaderVariableType,
+    },
+}
+
+impl ShaderConstants {
+    pub fn new<I: Input>(
+        desc: &ShaderConstantBufferDesc,
+        window: &mut crate::Window<I>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        // Reflection reports cbuffer sizes already rounded up to a whole register
+        let buffer_desc = win32::D3D11BufferDesc::new(
+            desc.size() as u32,
+            win32::D3D11Usage::Dynamic,
+            &[win32::D3D11BindFlag::ConstantBuffer],
+            &[win32::D3D11CPUAccessFlag::Write],
+            &[],
+            0,
+        );
+
+        let constant_buffer = window.device().create_buffer(&buffer_desc, None)?;
+
+        Ok(ShaderConstants {
+            data: ShaderConstantData::new(desc),
+            dirty: true,
+            constant_buffer,
+            slot: desc.slot(),
+            stages: desc.stages(),
+            device_context: window.device_context().clone(),
+        })
+    }
+
+    pub fn desc(&self) -> &ShaderConstantBufferDesc {
+        self.data.desc()
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f32) -> Result<(), ShaderConstantError> {
+        self.write(name, ShaderScalarType::Float, 1, 1, &[value.to_bits()])
+    }
+
+    pub fn set_int(&mut self, name: &str, value: i32) -> Result<(), ShaderConstantError> {
+        self.write(name, ShaderScalarType::Int, 1, 1, &[value as u32])
+    }
+
+    pub fn set_uint(&mut self, name: &str, value: u32) -> Result<(), ShaderConstantError> {
+        self.write(name, ShaderScalarType::UInt, 1, 1, &[value])
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) -> Result<(), ShaderConstantError> {
+        self.write(name, ShaderScalarType::Bool, 1, 1, &[value as u32])
+    }
+
+    pub fn set_vector2(&mut self, name: &str, value: Vector2) -> Result<(), ShaderConstantError> {
+        self.write(
+            name,
+            ShaderScalarType::Float,
+            1,
+            2,
+            &[value.x().to_bits(), value.y().to_bits()],
+        )
+    }
+
+    pub fn set_vector3(&mut self, name: &str, value: Vector3) -> Result<(), ShaderConstantError> {
+        self.write(
+            name,
+            ShaderScalarType::Float,
+            1,
+            3,
+            &[
+                value.x().to_bits(),
+                value.y().to_bits(),
+                value.z().to_bits(),
+            ],
+        )
+    }
+
+    pub fn set_vector4(&mut self, name: &str, value: Vector4) -> Result<(), ShaderConstantError> {
+        self.write(
+            name,
+            ShaderScalarType::Float,
+            1,
+            4,
+            &[
+                value.x().to_bits(),
+                value.y().to_bits(),
+                value.z().to_bits(),
+                value.w().to_bits(),
+            ],
+        )
+    }
+
+    // Writes the elements in memory order, exactly as ConstantBuffer<LHRowMajorMatrix> does, so
+    // the shader sees the same matrix through either path whichever packing it declares
+    pub fn set_matrix(
+        &mut self,
+        name: &str,
+        value: &LHRowMajorMatrix,
+    ) -> Result<(), ShaderConstantError> {
+        let elements: [f32; 4 * 4] = (*value).into();
+        self.write(
+            name,
+            ShaderScalarType::Float,
+            4,
+            4,
+            &elements.map(f32::to_bits),
+        )
+    }
+
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    pub fn set_slot(&mut self, slot: usize) {
+        self.slot = slot;
+    }
+
+    pub fn stages(&self) -> ShaderStages {
+        self.stages
+    }
+
+    pub fn set_stages(&mut self, stages: ShaderStages) {
+        self.stages = stages;
+    }
+
+    // Uploads any changed constants and binds the buffer
+    pub fn set_active(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut device_context = self.device_context.borrow_mut();
+
+        if self.dirty {
+            let mut mapped_resource = device_context.map(
+                &mut self.constant_buffer,
+                0,
+                win32::D3D11Map::WriteDiscard,
+                &[],
+            )?;
+
+            let base = mapped_resource.as_ref::<u8>() as *mut u8;
+            let data = self.data.data();
+            unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), base, data.len()) };
+
+            self.dirty = false;
+        }
+
+        self.stages.set_constant_buffers(
+            &mut device_context,
+            self.slot as u32,
+            &mut [Some(&mut self.constant_buffer)],
+        );
+
+        Ok(())
+    }
+
+    pub fn clear_active(&mut self) {
+        self.stages.set_constant_buffers(
+            &mut self.device_context.borrow_mut(),
+            self.slot as u32,
+            &mut [None],
+        );
+    }
+
+    fn write(
+        &mut self,
+        name: &str,
+        scalar: ShaderScalarType,
+        rows: usize,
+        columns: usize,
+        values: &[u32],
+    ) -> Result<(), ShaderConstantError> {
+        self.data.write(name, scalar, rows, columns, values)?;
+        self.dirty = true;
+        Ok(())
+    }
+}
+
+impl std::error::Error for ShaderConstantError {}
+
+impl std::fmt::Display for ShaderConstantError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderConstantError::UnknownVariable(name) => {
+                write!(f, "The shader has no constant named \"{}\"", name)
+            }
+            ShaderConstantError::TypeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "The shader constant \"{}\" is a {}, not a {}",
+                name, found, expected
+            ),
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use crate::ShaderStages;
+use win32::{DirectXError, ID3DBlob};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderVariableClass {
+    Scalar,
+    Vector,
+    MatrixRows,
+    MatrixColumns,
+    Struct,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderScalarType {
+    Float,
+    Int,
+    UInt,
+    Bool,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShaderVariableType {
+    class: ShaderVariableClass,
+    scalar: ShaderScalarType,
+    rows: usize,
+    columns: usize,
+
+    // Zero when the variable is not an array
+    elements: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ShaderVariable {
+    name: String,
+    offset: usize,
+    size: usize,
+    variable_type: ShaderVariableType,
+}
+
+// A cbuffer declared by a shader, merged across every stage which declares it
+#[derive(Debug, Clone)]
+pub struct ShaderConstantBufferDesc {
+    name: String,
+    size: usize,
+    slot: usize,
+    stages: ShaderStages,
+    variables: Vec<ShaderVariable>,
+}
+
+// An input of the vertex shader which is fed from vertex buffers
+#[derive(Debug, Clone)]
+pub struct ShaderInputParameter {
+    semantic_name: String,
+    semantic_index: usize,
+    scalar: ShaderScalarType,
+    components: usize,
+    read_components: usize,
+}
+
+// Adds the cbuffers declared by a compiled shader blob to constant_buffers
+pub(crate) fn reflect_constant_buffers(
+    blob: &ID3DBlob,
+    stage: ShaderStages,
+    constant_buffers: &mut Vec<ShaderConstantBufferDesc>,
+) -> Result<(), DirectXError> {
+    let reflection = win32::d3d_reflect::<win32::ID3D11ShaderReflection>(blob)?;
+    let shader_desc = reflection.get_desc()?;
+
+    for i in 0..shader_desc.constant_buffers() {
+        let constant_buffer = reflection.get_constant_buffer_by_index(i);
+        let buffer_desc = constant_buffer.get_desc()?;
+
+        // Texture buffers are bound as shader resources instead
+        if buffer_desc.buffer_type() != win32::D3DCBufferType::CBuffer {
+            continue;
+        }
+
+        let name = buffer_desc.name().to_string();
+
+        if let Some(existing) = constant_buffers
+            .iter_mut()
+            .find(|constant_buffer| constant_buffer.name == name)
+        {
+            existing.stages |= stage;
+            continue;
+        }
+
+        let binding_desc = reflection.get_resource_binding_desc_by_name(buffer_desc.name())?;
+
+        let mut variables = Vec::with_capacity(buffer_desc.variables() as usize);
+        for j in 0..buffer_desc.variables() {
+            let variable = constant_buffer.get_variable_by_index(j);
+            let variable_desc = variable.get_desc()?;
+            let type_desc = variable.get_type().get_desc()?;
+
+            variables.push(ShaderVariable {
+                name: variable_desc.name().to_string(),
+                offset: variable_desc.start_offset() as usize,
+                size: variable_desc.size() as usize,
+                variable_type: ShaderVariableType {
+                    class: match type_desc.class() {
+                        win32::D3DShaderVariableClass::Scalar => ShaderVariableClass::Scalar,
+                        win32::D3DShaderVariableClass::Vector => ShaderVariableClass::Vector,
+                        win32::D3DShaderVariableClass::MatrixRows => {
+                            ShaderVariableClass::MatrixRows
+                        }
+                        win32::D3DShaderVariableClass::MatrixColumns => {
+                            ShaderVariableClass::MatrixColumns
+                        }
+                        win32::D3DShaderVariableClass::Struct => ShaderVariableClass::Struct,
+                        _ => ShaderVariableClass::Other,
+                    },
+                    scalar: match type_desc.variable_type() {
+                        win32::D3DShaderVariableType::Float => ShaderScalarType::Float,
+                        win32::D3DShaderVariableType::Int => ShaderScalarType::Int,
+                        win32::D3DShaderVariableType::UInt => ShaderScalarType::UInt,
+                        win32::D3DShaderVariableType::Bool => ShaderScalarType::Bool,
+                        _ => ShaderScalarType::Other,
+                    },
+                    rows: type_desc.rows() as usize,
+                    columns: type_desc.columns() as usize,
+                    elements: type_desc.elements() as usize,
+                },
+            });
+        }
+
+        constant_buffers.push(ShaderConstantBufferDesc {
+            name,
+            size: buffer_desc.size() as usize,
+            slot: binding_desc.bind_point() as usize,
+            stages: stage,
+            variables,
+        });
+    }
+
+    Ok(())
+}
+
+// Reads the input signature of a compiled vertex shader, skipping system values such as
+// SV_VertexID which are generated by the input assembler
+pub(crate) fn reflect_input_signature(
+    blob: &ID3DBlob,
+) -> Result<Vec<ShaderInputParameter>, DirectXError> {
+    let reflection = win32::d3d_reflect::<win32::ID3D11ShaderReflection>(blob)?;
+    let shader_desc = reflection.get_desc()?;
+
+    let mut parameters = Vec::with_capacity(shader_desc.input_parameters() as usize);
+    for i in 0..shader_desc.input_parameters() {
+        let parameter_desc = reflection.get_input_parameter_desc(i)?;
+        if parameter_desc.system_value_type() != win32::D3DName::Undefined {
+            continue;
+        }
+
+        parameters.push(ShaderInputParameter::new(
+            parameter_desc.semantic_name(),
+            parameter_desc.semantic_index() as usize,
+            match parameter_desc.component_type() {
+                win32::D3DRegisterComponentType::Float32 => ShaderScalarType::Float,
+                win32::D3DRegisterComponentType::SInt32 => ShaderScalarType::Int,
+                win32::D3DRegisterComponentType::UInt32 => ShaderScalarType::UInt,
+                _ => ShaderScalarType::Other,
+            },
+            // The mask covers every component the input declares, even unread ones, while the
+            // read mask only has the ones the shader uses
+            parameter_desc.mask().count_ones() as usize,
+            (0..4)
+                .rev()
+                .find(|i| parameter_desc.read_write_mask() & (1 << i) != 0)
+                .map_or(0, |i| i + 1),
+        ));
+    }
+
+    Ok(parameters)
+}
+
+pub(crate) fn reflect_thread_group_size(blob: &ID3DBlob) -> Result<[u32; 3], DirectXError> {
+    let reflection = win32::d3d_reflect::<win32::ID3D11ShaderReflection>(blob)?;
+    let (x, y, z) = reflection.get_thread_group_size();
+    Ok([x, y, z])
+}
+
+impl ShaderVariableType {
+    // A non-array scalar, vector or row major matrix type
+    pub const fn new(scalar: ShaderScalarType, rows: usize, columns: usize) -> Self {
+        ShaderVariableType {
+            class: match (rows, columns) {
+                (1, 1) => ShaderVariableClass::Scalar,
+                (1, _) => ShaderVariableClass::Vector,
+                _ => ShaderVariableClass::MatrixRows,
+            },
+            scalar,
+            rows,
+            columns,
+            elements: 0,
+        }
+    }
+
+    // A non-array column major matrix type
+    pub(crate) const fn column_major(
+        scalar: ShaderScalarType,
+        rows: usize,
+        columns: usize,
+    ) -> Self {
+        ShaderVariableType {
+            class: ShaderVariableClass::MatrixColumns,
+            scalar,
+            rows,
+            columns,
+            elements: 0,
+        }
+    }
+
+    pub fn class(&self) -> ShaderVariableClass {
+        self.class
+    }
+
+    pub fn scalar(&self) -> ShaderScalarType {
+        self.scalar
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn elements(&self) -> usize {
+        self.elements
+    }
+
+    // Whether the types hold the same values, regardless of how a matrix is packed
+    pub fn matches(&self, other: &ShaderVariableType) -> bool {
+        self.scalar == other.scalar
+            && self.rows == other.rows
+            && self.columns == other.columns
+            && self.elements == other.elements
+    }
+}
+
+impl ShaderVariable {
+    pub(crate) fn new(
+        name: &str,
+        offset: usize,
+        size: usize,
+        variable_type: ShaderVariableType,
+    ) -> Self {
+        ShaderVariable {
+            name: name.to_string(),
+            offset,
+            size,
+            variable_type,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // In bytes from the start of the cbuffer
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn variable_type(&self) -> &ShaderVariableType {
+        &self.variable_type
+    }
+}
+
+impl ShaderInputParameter {
+    pub(crate) fn new(
+        semantic_name: &str,
+        semantic_index: usize,
+        scalar: ShaderScalarType,
+        components: usize,
+        read_components: usize,
+    ) -> Self {
+        ShaderInputParameter {
+            semantic_name: semantic_name.to_string(),
+            semantic_index,
+            scalar,
+            components,
+            read_components,
+        }
+    }
+
+    pub fn semantic_name(&self) -> &str {
+        &self.semantic_name
+    }
+
+    pub fn semantic_index(&self) -> usize {
+        self.semantic_index
+    }
+
+    pub fn scalar(&self) -> ShaderScalarType {
+        self.scalar
+    }
+
+    // The number of components the input declares
+    pub fn components(&self) -> usize {
+        self.components
+    }
+
+    // The number of components up to and including the last one the shader reads, so reading
+    // only .z counts as 3
+    pub fn read_components(&self) -> usize {
+        self.read_components
+    }
+}
+
+impl ShaderConstantBufferDesc {
+    pub(crate) fn new(
+        name: &str,
+        size: usize,
+        slot: usize,
+        stages: ShaderStages,
+        variables: Vec<ShaderVariable>,
+    ) -> Self {
+        ShaderConstantBufferDesc {
+            name: name.to_string(),
+            size,
+            slot,
+            stages,
+            variables,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    pub fn stages(&self) -> ShaderStages {
+        self.stages
+    }
+
+    pub fn variables(&self) -> &[ShaderVariable] {
+        &self.variables
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&ShaderVariable> {
+        self.variables.iter().find(|variable| variable.name == name)
+    }
+}
+
+impl std::fmt::Display for ShaderVariableType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.class == ShaderVariableClass::Struct {
+            write!(f, "struct")?;
+        } else {
+            write!(
+                f,
+                "{}",
+                match self.scalar {
+                    ShaderScalarType::Float => "float",
+                    ShaderScalarType::Int => "int",
+                    ShaderScalarType::UInt => "uint",
+                    ShaderScalarType::Bool => "bool",
+                    ShaderScalarType::Other => "unknown",
+                }
+            )?;
+
+            match self.class {
+                ShaderVariableClass::Vector => write!(f, "{}", self.columns)?,
+                ShaderVariableClass::MatrixRows | ShaderVariableClass::MatrixColumns => {
+                    write!(f, "{}x{}", self.rows, self.columns)?
+                }
+                _ => {}
+            }
+        }
+
+        if self.elements > 0 {
+            write!(f, "[{}]", self.elements)?;
+        }
+
+        Ok(())
+    }
+}
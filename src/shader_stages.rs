@@ -0,0 +1,184 @@
+use std::ops::{BitAnd, BitOr, BitOrAssign, Not};
+
+// A set of shader stages a resource is bound to. Resources bind to every stage in their set
+// when set active, so changing the set only takes effect the next time the resource is set
+// active, and stages removed from the set stay bound until cleared. Clear the resource before
+// changing its stages to avoid that.
+//
+// Compute read-write access goes through unordered access views rather than these stages. A
+// resource cannot be read and written at the same time, so binding it for compute read-write
+// makes D3D11 unbind it from every shader resource slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShaderStages(u8);
+
+impl ShaderStages {
+    pub const NONE: ShaderStages = ShaderStages(0);
+    pub const VERTEX: ShaderStages = ShaderStages(1 << 0);
+    pub const PIXEL: ShaderStages = ShaderStages(1 << 1);
+    pub const GEOMETRY: ShaderStages = ShaderStages(1 << 2);
+    pub const HULL: ShaderStages = ShaderStages(1 << 3);
+    pub const DOMAIN: ShaderStages = ShaderStages(1 << 4);
+    pub const COMPUTE: ShaderStages = ShaderStages(1 << 5);
+
+    pub const ALL_GRAPHICS: ShaderStages = ShaderStages(
+        ShaderStages::VERTEX.0
+            | ShaderStages::PIXEL.0
+            | ShaderStages::GEOMETRY.0
+            | ShaderStages::HULL.0
+            | ShaderStages::DOMAIN.0,
+    );
+    pub const ALL: ShaderStages =
+        ShaderStages(ShaderStages::ALL_GRAPHICS.0 | ShaderStages::COMPUTE.0);
+
+    pub const fn contains(&self, stages: ShaderStages) -> bool {
+        self.0 & stages.0 == stages.0
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub(crate) fn set_constant_buffers(
+        &self,
+        device_context: &mut win32::ID3D11DeviceContext,
+        slot: u32,
+        buffers: &mut [Option<&mut win32::ID3D11Buffer>],
+    ) {
+        if self.contains(ShaderStages::VERTEX) {
+            device_context.vs_set_constant_buffers(slot, buffers);
+        }
+        if self.contains(ShaderStages::PIXEL) {
+            device_context.ps_set_constant_buffers(slot, buffers);
+        }
+        if self.contains(ShaderStages::GEOMETRY) {
+            device_context.gs_set_constant_buffers(slot, buffers);
+        }
+        if self.contains(ShaderStages::HULL) {
+            device_context.hs_set_constant_buffers(slot, buffers);
+        }
+        if self.contains(ShaderStages::DOMAIN) {
+            device_context.ds_set_constant_buffers(slot, buffers);
+        }
+        if self.contains(ShaderStages::COMPUTE) {
+            device_context.cs_set_constant_buffers(slot, buffers);
+        }
+    }
+
+    // Binds ranges of constant buffers, measured in 16 byte constants, on D3D11.1
+    pub(crate) fn set_constant_buffers1(
+        &self,
+        device_context: &mut win32::ID3D11DeviceContext1,
+        slot: u32,
+        buffers: &mut [Option<&mut win32::ID3D11Buffer>],
+        first_constants: &[u32],
+        num_constants: &[u32],
+    ) {
+        if self.contains(ShaderStages::VERTEX) {
+            device_context.vs_set_constant_buffers1(slot, buffers, first_constants, num_constants);
+        }
+        if self.contains(ShaderStages::PIXEL) {
+            device_context.ps_set_constant_buffers1(slot, buffers, first_constants, num_constants);
+        }
+        if self.contains(ShaderStages::GEOMETRY) {
+            device_context.gs_set_constant_buffers1(slot, buffers, first_constants, num_constants);
+        }
+        if self.contains(ShaderStages::HULL) {
+            device_context.hs_set_constant_buffers1(slot, buffers, first_constants, num_constants);
+        }
+        if self.contains(ShaderStages::DOMAIN) {
+            device_context.ds_set_constant_buffers1(slot, buffers, first_constants, num_constants);
+        }
+        if self.contains(ShaderStages::COMPUTE) {
+            device_context.cs_set_constant_buffers1(slot, buffers, first_constants, num_constants);
+        }
+    }
+
+    pub(crate) fn set_shader_resources(
+        &self,
+        device_context: &mut win32::ID3D11DeviceContext,
+        slot: u32,
+        views: &mut [Option<&mut win32::ID3D11ShaderResourceView>],
+    ) {
+        if self.contains(ShaderStages::VERTEX) {
+            device_context.vs_set_shader_resources(slot, views);
+        }
+        if self.contains(ShaderStages::PIXEL) {
+            device_context.ps_set_shader_resources(slot, views);
+        }
+        if self.contains(ShaderStages::GEOMETRY) {
+            device_context.gs_set_shader_resources(slot, views);
+        }
+        if self.contains(ShaderStages::HULL) {
+            device_context.hs_set_shader_resources(slot, views);
+        }
+        if self.contains(ShaderStages::DOMAIN) {
+            device_context.ds_set_shader_resources(slot, views);
+        }
+        if self.contains(ShaderStages::COMPUTE) {
+            device_context.cs_set_shader_resources(slot, views);
+        }
+    }
+
+    pub(crate) fn set_samplers(
+        &self,
+        device_context: &mut win32::ID3D11DeviceContext,
+        slot: u32,
+        samplers: &mut [Option<&mut win32::ID3D11SamplerState>],
+    ) {
+        if self.contains(ShaderStages::VERTEX) {
+            device_context.vs_set_samplers(slot, samplers);
+        }
+        if self.contains(ShaderStages::PIXEL) {
+            device_context.ps_set_samplers(slot, samplers);
+        }
+        if self.contains(ShaderStages::GEOMETRY) {
+            device_context.gs_set_samplers(slot, samplers);
+        }
+        if self.contains(ShaderStages::HULL) {
+            device_context.hs_set_samplers(slot, samplers);
+        }
+        if self.contains(ShaderStages::DOMAIN) {
+            device_context.ds_set_samplers(slot, samplers);
+        }
+        if self.contains(ShaderStages::COMPUTE) {
+            device_context.cs_set_samplers(slot, samplers);
+        }
+    }
+}
+
+// The vertex and pixel stages, which most resources are read from
+impl Default for ShaderStages {
+    fn default() -> Self {
+        ShaderStages::VERTEX | ShaderStages::PIXEL
+    }
+}
+
+impl BitOr for ShaderStages {
+    type Output = ShaderStages;
+
+    fn bitor(self, rhs: ShaderStages) -> ShaderStages {
+        ShaderStages(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for ShaderStages {
+    fn bitor_assign(&mut self, rhs: ShaderStages) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for ShaderStages {
+    type Output = ShaderStages;
+
+    fn bitand(self, rhs: ShaderStages) -> ShaderStages {
+        ShaderStages(self.0 & rhs.0)
+    }
+}
+
+impl Not for ShaderStages {
+    type Output = ShaderStages;
+
+    fn not(self) -> ShaderStages {
+        ShaderStages(!self.0 & ShaderStages::ALL.0)
+    }
+}
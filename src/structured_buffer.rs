@@ -0,0 +1,160 @@
+use crate::{structured_stride, HLSLStructuredType, ShaderStages};
+use alexandria_common::Input;
+use std::{cell::RefCell, marker::PhantomData, rc::Rc};
+
+// Elements are copied into the buffer byte for byte, which HLSLStructuredType guarantees is
+// sound and matches how HLSL reads them
+pub struct StructuredBuffer<T: HLSLStructuredType> {
+    buffer: win32::ID3D11Buffer,
+    srv: win32::ID3D11ShaderResourceView,
+    uav: win32::ID3D11UnorderedAccessView,
+    count: usize,
+    slot: usize,
+    stages: ShaderStages,
+    phantom: PhantomData<T>,
+    device: Rc<win32::ID3D11Device>,
+    device_context: Rc<RefCell<win32::ID3D11DeviceContext>>,
+}
+
+#[derive(Debug)]
+pub struct EmptyStructuredBufferError;
+
+type BufferAndViews = (
+    win32::ID3D11Buffer,
+    win32::ID3D11ShaderResourceView,
+    win32::ID3D11UnorderedAccessView,
+);
+
+impl<T: HLSLStructuredType> StructuredBuffer<T> {
+    const STRIDE: usize = structured_stride::<T>();
+
+    pub fn new<I: Input>(
+        data: &[T],
+        slot: usize,
+        window: &mut crate::Window<I>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let (buffer, srv, uav) = StructuredBuffer::create(window.device(), data)?;
+
+        Ok(StructuredBuffer {
+            buffer,
+            srv,
+            uav,
+            count: data.len(),
+            slot,
+            stages: ShaderStages::default(),
+            phantom: PhantomData,
+            device: window.device().clone(),
+            device_context: window.device_context().clone(),
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    // Updates the buffer in place when the number of elements is unchanged, otherwise the
+    // buffer is recreated and needs to be set active again
+    pub fn set_data(&mut self, data: &[T]) -> Result<(), Box<dyn std::error::Error>> {
+        if data.len() != self.count {
+            let (buffer, srv, uav) = StructuredBuffer::create(&self.device, data)?;
+            self.buffer = buffer;
+            self.srv = srv;
+            self.uav = uav;
+            self.count = data.len();
+            return Ok(());
+        }
+
+        self.device_context
+            .borrow_mut()
+            .update_subresource(&mut self.buffer, 0, None, data, 0, 0);
+
+        Ok(())
+    }
+
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+
+    pub fn set_slot(&mut self, slot: usize) {
+        self.slot = slot;
+    }
+
+    pub fn stages(&self) -> ShaderStages {
+        self.stages
+    }
+
+    // Takes effect the next time it is set active, see ShaderStages
+    pub fn set_stages(&mut self, stages: ShaderStages) {
+        self.stages = stages;
+    }
+
+    pub fn set_active(&mut self) {
+        self.stages.set_shader_resources(
+            &mut self.device_context.borrow_mut(),
+            self.slot as u32,
+            &mut [Some(&mut self.srv)],
+        );
+    }
+
+    pub fn clear_active(&mut self) {
+        self.stages.set_shader_resources(
+            &mut self.device_context.borrow_mut(),
+            self.slot as u32,
+            &mut [None],
+        );
+    }
+
+    // Unbinds it from any shader resource slots, see ShaderStages
+    pub fn set_active_compute_rw(&mut self) {
+        self.device_context
+            .borrow_mut()
+            .cs_set_unordered_access_views(self.slot as u32, &mut [Some(&mut self.uav)]);
+    }
+
+    pub fn clear_active_compute_rw(&mut self) {
+        self.device_context
+            .borrow_mut()
+            .cs_set_unordered_access_views(self.slot as u32, &mut [None]);
+    }
+
+    fn create(
+        device: &win32::ID3D11Device,
+        data: &[T],
+    ) -> Result<BufferAndViews, Box<dyn std::error::Error>> {
+        if data.is_empty() {
+            return Err(Box::new(EmptyStructuredBufferError));
+        }
+
+        let buffer_desc = win32::D3D11BufferDesc::new(
+            (Self::STRIDE * data.len()) as u32,
+            win32::D3D11Usage::Default,
+            &[
+                win32::D3D11BindFlag::ShaderResource,
+                win32::D3D11BindFlag::UnorderedAccess,
+            ],
+            &[],
+            &[win32::D3D11ResourceMiscFlag::BufferStructured],
+            Self::STRIDE as u32,
+        );
+
+        let initial_data = win32::D3D11SubresourceData::new(data, 0, 0);
+
+        let mut buffer = device.create_buffer(&buffer_desc, Some(&initial_data))?;
+
+        let srv_desc = win32::D3D11ShaderResourceViewDesc::new_buffer(0, data.len() as u32);
+        let srv = device.create_shader_resource_view(&mut buffer, &srv_desc)?;
+
+        let uav_desc = win32::D3D11UnorderedAccessViewDesc::new_buffer(0, data.len() as u32);
+        let uav = device.create_unordered_access_view(&mut buffer, &uav_desc)?;
+
+        Ok((buffer, srv, uav))
+    }
+}
+
+impl std::error::Error for EmptyStructuredBufferError {}
+
+impl std::fmt::Display for EmptyStructuredBufferError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "A structured buffer must hold at least one element")
+    }
+}
@@ -1,3 +1,4 @@
+use crate::ShaderStages;
 use alexandria_common::{Input, SampleType, TextureFormat, TextureFormatClass};
 use std::{cell::RefCell, marker::PhantomData, rc::Rc};
 use win32::DXGIFormat;
@@ -6,9 +7,10 @@ pub struct Texture2D<F: TextureFormat> {
     texture: win32::ID3D11Texture2D,
     sampler: win32::ID3D11SamplerState,
     srv: win32::ID3D11ShaderResourceView,
-    _uav: win32::ID3D11UnorderedAccessView,
+    uav: win32::ID3D11UnorderedAccessView,
     device_context: Rc<RefCell<win32::ID3D11DeviceContext>>,
     slot: usize,
+    stages: ShaderStages,
 
     phantom: PhantomData<F>,
 }
@@ -71,7 +73,7 @@ impl<F: TextureFormat> alexandria_common::Texture2D<F> for Texture2D<F> {
 
         let uav_desc = win32::D3D11UnorderedAccessViewDesc::new(format, &mut texture);
 
-        let _uav = window
+        let uav = window
             .device()
             .create_unordered_access_view(&mut texture, &uav_desc)?;
 
@@ -86,8 +88,9 @@ impl<F: TextureFormat> alexandria_common::Texture2D<F> for Texture2D<F> {
             texture,
             sampler,
             srv,
-            _uav,
+            uav,
             slot,
+            stages: ShaderStages::default(),
             device_context: window.device_context().clone(),
             phantom: PhantomData,
         })
@@ -99,34 +102,26 @@ impl<F: TextureFormat> alexandria_common::Texture2D<F> for Texture2D<F> {
 
     fn set_active(&mut self) {
         let mut device_context = self.device_context.borrow_mut();
-        device_context.vs_set_shader_resources(self.slot as u32, &mut [Some(&mut self.srv)]);
-        device_context.ps_set_shader_resources(self.slot as u32, &mut [Some(&mut self.srv)]);
-        device_context.vs_set_samplers(self.slot as u32, &mut [Some(&mut self.sampler)]);
-        device_context.ps_set_samplers(self.slot as u32, &mut [Some(&mut self.sampler)]);
+        self.stages.set_shader_resources(
+            &mut device_context,
+            self.slot as u32,
+            &mut [Some(&mut self.srv)],
+        );
+        self.stages.set_samplers(
+            &mut device_context,
+            self.slot as u32,
+            &mut [Some(&mut self.sampler)],
+        );
     }
 
     fn clear_active(&mut self) {
         let mut device_context = self.device_context.borrow_mut();
-        device_context.vs_set_shader_resources(self.slot as u32, &mut [None]);
-        device_context.ps_set_shader_resources(self.slot as u32, &mut [None]);
-        device_context.vs_set_samplers(self.slot as u32, &mut [None]);
-        device_context.ps_set_samplers(self.slot as u32, &mut [None]);
+        self.stages
+            .set_shader_resources(&mut device_context, self.slot as u32, &mut [None]);
+        self.stages
+            .set_samplers(&mut device_context, self.slot as u32, &mut [None]);
     }
 
-    /*
-    fn set_active_compute(&mut self) {
-        self.device_context
-            .borrow_mut()
-            .cs_set_shader_resources(self.slot as u32, &mut [Some(&mut self.srv)])
-    }
-
-    fn set_active_compute_rw(&mut self) {
-        self.device_context
-            .borrow_mut()
-            .cs_set_unordered_access_views(self.slot as u32, &mut [Some(&mut self.uav)]);
-    }
-    */
-
     fn update_region(&mut self, region: alexandria_common::UpdateRegion, data: &[F]) {
         self.device_context.borrow_mut().update_subresource(
             &mut self.texture,
@@ -145,3 +140,27 @@ impl<F: TextureFormat> alexandria_common::Texture2D<F> for Texture2D<F> {
         )
     }
 }
+
+impl<F: TextureFormat> Texture2D<F> {
+    pub fn stages(&self) -> ShaderStages {
+        self.stages
+    }
+
+    // Takes effect the next time it is set active, see ShaderStages
+    pub fn set_stages(&mut self, stages: ShaderStages) {
+        self.stages = stages;
+    }
+
+    // Unbinds it from any shader resource slots, see ShaderStages
+    pub fn set_active_compute_rw(&mut self) {
+        self.device_context
+            .borrow_mut()
+            .cs_set_unordered_access_views(self.slot as u32, &mut [Some(&mut self.uav)]);
+    }
+
+    pub fn clear_active_compute_rw(&mut self) {
+        self.device_context
+            .borrow_mut()
+            .cs_set_unordered_access_views(self.slot as u32, &mut [None]);
+    }
+}
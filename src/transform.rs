@@ -0,0 +1,94 @@
+use crate::{LHRowMajorMatrix, Quaternion};
+use alexandria_common::Vector3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Transform {
+    translation: Vector3,
+    rotation: Quaternion,
+    scale: Vector3,
+}
+
+fn lerp(start: Vector3, end: Vector3, t: f32) -> Vector3 {
+    Vector3::new(
+        start.x() + (end.x() - start.x()) * t,
+        start.y() + (end.y() - start.y()) * t,
+        start.z() + (end.z() - start.z()) * t,
+    )
+}
+
+impl Transform {
+    pub fn new(translation: Vector3, rotation: Quaternion, scale: Vector3) -> Self {
+        Transform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn identity() -> Self {
+        Transform::new(
+            Vector3::new(0.0, 0.0, 0.0),
+            Quaternion::identity(),
+            Vector3::new(1.0, 1.0, 1.0),
+        )
+    }
+
+    pub fn from_matrix(matrix: &LHRowMajorMatrix) -> Option<Self> {
+        matrix
+            .decompose()
+            .map(|(translation, rotation, scale)| Transform::new(translation, rotation, scale))
+    }
+
+    pub fn to_matrix(&self) -> LHRowMajorMatrix {
+        LHRowMajorMatrix::from_trs(self.translation, self.rotation, self.scale)
+    }
+
+    pub fn translation(&self) -> Vector3 {
+        self.translation
+    }
+
+    pub fn rotation(&self) -> Quaternion {
+        self.rotation
+    }
+
+    pub fn scale(&self) -> Vector3 {
+        self.scale
+    }
+
+    pub fn set_translation(&mut self, translation: Vector3) {
+        self.translation = translation;
+    }
+
+    pub fn set_rotation(&mut self, rotation: Quaternion) {
+        self.rotation = rotation;
+    }
+
+    pub fn set_scale(&mut self, scale: Vector3) {
+        self.scale = scale;
+    }
+
+    // Lerps translation and scale and slerps rotation
+    pub fn interpolate(&self, target: &Transform, t: f32) -> Transform {
+        Transform::new(
+            lerp(self.translation, target.translation, t),
+            self.rotation.slerp(target.rotation, t),
+            lerp(self.scale, target.scale, t),
+        )
+    }
+}
+
+impl LHRowMajorMatrix {
+    // Interpolates the decomposed translation, rotation and scale of two matrices. Returns None
+    // if either matrix cannot be decomposed.
+    pub fn interpolate(&self, target: &LHRowMajorMatrix, t: f32) -> Option<LHRowMajorMatrix> {
+        let start = Transform::from_matrix(self)?;
+        let end = Transform::from_matrix(target)?;
+        Some(start.interpolate(&end, t).to_matrix())
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
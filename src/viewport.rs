@@ -1,8 +1,11 @@
+use crate::{cursor_to_screen, LHRowMajorMatrix, Ray};
 use alexandria_common::{Vector2, ViewportUpdater};
 use std::{cell::RefCell, rc::Rc};
 
 pub struct Viewport {
     viewport: win32::D3D11Viewport,
+    top_left: Vector2,
+    size: Vector2,
     updater: Option<Box<dyn ViewportUpdater>>,
     device_context: Rc<RefCell<win32::ID3D11DeviceContext>>,
     key: usize,
@@ -25,12 +28,39 @@ impl Viewport {
                 0.0,
                 1.0,
             ),
+            top_left,
+            size,
             updater,
             device_context,
             key,
         }
     }
 
+    pub fn top_left(&self) -> Vector2 {
+        self.top_left
+    }
+
+    pub fn size(&self) -> Vector2 {
+        self.size
+    }
+
+    // Builds the world space ray under the mouse position reported to Input
+    pub fn ray_from_cursor(
+        &self,
+        cursor: (isize, isize),
+        window_size: (usize, usize),
+        view: &LHRowMajorMatrix,
+        projection: &LHRowMajorMatrix,
+    ) -> Option<Ray> {
+        Ray::from_screen(
+            cursor_to_screen(cursor, window_size),
+            self.top_left,
+            self.size,
+            view,
+            projection,
+        )
+    }
+
     pub(crate) fn updater(&mut self) -> Option<&mut Box<dyn ViewportUpdater>> {
         self.updater.as_mut()
     }
@@ -50,5 +80,7 @@ impl alexandria_common::Viewport for Viewport {
     fn update(&mut self, top_left: alexandria_common::Vector2, size: alexandria_common::Vector2) {
         self.viewport =
             win32::D3D11Viewport::new(top_left.x(), top_left.y(), size.x(), size.y(), 0.0, 1.0);
+        self.top_left = top_left;
+        self.size = size;
     }
 }
@@ -1,4 +1,7 @@
-use crate::{graphics::Graphics, Viewport};
+use crate::{
+    graphics::{DepthMode, Graphics},
+    ShaderCache, Viewport,
+};
 use alexandria_common::{Input, Key, MouseButton, Vector2, Viewport as CommonViewport};
 use std::{cell::RefCell, ffi::CString, ptr::null, rc::Rc};
 use win32::RawInput;
@@ -11,6 +14,7 @@ pub struct Window<I: Input> {
     h_wnd: win32::HWnd,
     msg: win32::Msg,
     graphics: Option<Graphics>,
+    shader_cache: Option<ShaderCache>,
 
     mouse_center: (i32, i32),
     update_mouse_center: bool,
@@ -51,6 +55,40 @@ impl<I: Input> Window<I> {
         &self.graphics.as_ref().unwrap().device_context()
     }
 
+    pub fn get_viewport(&mut self, viewport: usize) -> Option<&Viewport> {
+        self.graphics
+            .as_mut()
+            .unwrap()
+            .get_viewport(viewport)
+            .map(|viewport| &*viewport)
+    }
+
+    pub fn depth_mode(&self) -> DepthMode {
+        self.graphics.as_ref().unwrap().depth_mode()
+    }
+
+    pub fn set_depth_mode(
+        &mut self,
+        depth_mode: DepthMode,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.graphics.as_mut().unwrap().set_depth_mode(
+            depth_mode,
+            self.width as u32,
+            self.height as u32,
+        )?;
+        Ok(())
+    }
+
+    pub fn shader_cache(&self) -> Option<&ShaderCache> {
+        self.shader_cache.as_ref()
+    }
+
+    // Shaders created afterwards load their bytecode from the cache when it matches their
+    // source, and store it there after compiling otherwise
+    pub fn set_shader_cache(&mut self, shader_cache: Option<ShaderCache>) {
+        self.shader_cache = shader_cache;
+    }
+
     fn wnd_proc(
         &mut self,
         h_wnd: win32::HWnd,
@@ -203,6 +241,7 @@ impl<I: Input> alexandria_common::Window<I> for Box<Window<I>> {
             msg: win32::Msg::default(),
             input: I::new(),
             graphics: None,
+            shader_cache: None,
             width,
             height,
             mouse_center: (0, 0),